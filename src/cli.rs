@@ -0,0 +1,394 @@
+use rand::prelude::*;
+use std::fmt::Display;
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use the_witness::gui::ShellMode;
+use the_witness::puzzle_logic::*;
+use the_witness::svg::render_svg;
+
+pub const USAGE: &str = "\
+Usage: the_witness [command]
+
+Commands:
+  play <file>                       play puzzle
+  edit <file>                       edit puzzle, file is created on first save
+  check <puzzle> <solution>         check solution written as printed by solve
+  solve <file> [--count] [--limit N]
+                                    print every solution, or only their number,
+                                    stops after N solutions
+  generate [--seed N] [--size WxH] [-o file]
+                                    generate random puzzle which has a solution,
+                                    sides are from 2 to 12
+  render <file> [-o out.svg]        draw puzzle as svg
+Without command the default puzzle is played.
+Output goes to stdout if there is no -o.
+
+Exit codes: 0 success, 1 wrong solution or no solutions, 2 wrong arguments, 3 file error";
+
+const DEFAULT_GENERATE_SIZE: (usize, usize) = (5, 5);
+const SVG_SIZE: f32 = 600.0;
+
+pub enum Command {
+    Gui {
+        path: Option<PathBuf>,
+        mode: ShellMode,
+    },
+    Check {
+        puzzle: PathBuf,
+        solution: PathBuf,
+    },
+    Solve {
+        puzzle: PathBuf,
+        count: bool,
+        limit: Option<usize>,
+    },
+    Generate {
+        seed: Option<u64>,
+        size: (usize, usize),
+        output: Option<PathBuf>,
+    },
+    Render {
+        puzzle: PathBuf,
+        output: Option<PathBuf>,
+    },
+    Help,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, String),
+    /// Command worked, but answer is negative, like wrong solution
+    Failed(String),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{message}\n\n{USAGE}"),
+            CliError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            CliError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            CliError::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Failed(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Io(..) | CliError::Parse(..) => 3,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    positional: Vec<String>,
+    count: bool,
+    limit: Option<usize>,
+    seed: Option<u64>,
+    size: Option<(usize, usize)>,
+    output: Option<PathBuf>,
+}
+
+fn usage_error(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+/// Both sides must be from 2 to `MAX_GRID_SIZE`
+fn parse_size(text: &str) -> Option<(usize, usize)> {
+    let (width, height) = text.split_once('x')?;
+    let size = (width.parse().ok()?, height.parse().ok()?);
+    let sides = 2..=MAX_GRID_SIZE;
+    (sides.contains(&size.0) && sides.contains(&size.1)).then_some(size)
+}
+
+/// Flags not in `allowed` are errors, `positional` is exact number of other arguments
+fn parse_options(args: &[String], allowed: &[&str], positional: usize) -> Result<Options, CliError> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            options.positional.push(arg.clone());
+            continue;
+        }
+        if !allowed.contains(&arg.as_str()) {
+            return Err(usage_error(format!("unexpected option {arg}")));
+        }
+        let mut value = || args.next().ok_or_else(|| usage_error(format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--count" => options.count = true,
+            "--limit" => {
+                let value = value()?;
+                let limit = value.parse().ok().filter(|&limit| limit > 0);
+                options.limit = Some(limit.ok_or_else(|| usage_error(format!("wrong limit {value}")))?);
+            }
+            "--seed" => {
+                let value = value()?;
+                options.seed = Some(value.parse().map_err(|_| usage_error(format!("wrong seed {value}")))?);
+            }
+            "--size" => {
+                let value = value()?;
+                options.size = Some(parse_size(value).ok_or_else(|| usage_error(format!("wrong size {value}")))?);
+            }
+            "-o" => options.output = Some(PathBuf::from(value()?)),
+            _ => unreachable!("allowed options are handled"),
+        }
+    }
+    if options.positional.len() != positional {
+        return Err(usage_error(format!(
+            "expected {positional} arguments, got {}",
+            options.positional.len()
+        )));
+    }
+    Ok(options)
+}
+
+/// `args` are without program name
+pub fn parse_args(args: &[String]) -> Result<Command, CliError> {
+    let Some((command, args)) = args.split_first() else {
+        return Ok(Command::Gui {
+            path: None,
+            mode: ShellMode::Play,
+        });
+    };
+    let command = match command.as_str() {
+        "play" | "edit" => {
+            let mut options = parse_options(args, &[], 1)?;
+            let mode = if command == "play" {
+                ShellMode::Play
+            } else {
+                ShellMode::Edit
+            };
+            Command::Gui {
+                path: Some(PathBuf::from(options.positional.remove(0))),
+                mode,
+            }
+        }
+        "check" => {
+            let mut options = parse_options(args, &[], 2)?;
+            let solution = PathBuf::from(options.positional.remove(1));
+            let puzzle = PathBuf::from(options.positional.remove(0));
+            Command::Check { puzzle, solution }
+        }
+        "solve" => {
+            let mut options = parse_options(args, &["--count", "--limit"], 1)?;
+            Command::Solve {
+                puzzle: PathBuf::from(options.positional.remove(0)),
+                count: options.count,
+                limit: options.limit,
+            }
+        }
+        "generate" => {
+            let options = parse_options(args, &["--seed", "--size", "-o"], 0)?;
+            Command::Generate {
+                seed: options.seed,
+                size: options.size.unwrap_or(DEFAULT_GENERATE_SIZE),
+                output: options.output,
+            }
+        }
+        "render" => {
+            let mut options = parse_options(args, &["-o"], 1)?;
+            Command::Render {
+                puzzle: PathBuf::from(options.positional.remove(0)),
+                output: options.output,
+            }
+        }
+        "help" | "--help" | "-h" => Command::Help,
+        _ => return Err(usage_error(format!("unknown command {command}"))),
+    };
+    Ok(command)
+}
+
+pub fn load_puzzle(path: &Path) -> Result<Puzzle, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|err| CliError::Io(path.to_path_buf(), err))?;
+    let puzzle = Puzzle::from_ron(&text).map_err(|err| CliError::Parse(path.to_path_buf(), err.to_string()))?;
+    match validate_puzzle(&puzzle).into_iter().find(ValidationWarning::is_broken) {
+        Some(warning) => Err(CliError::Parse(path.to_path_buf(), warning.to_string())),
+        None => Ok(puzzle),
+    }
+}
+
+fn write_output(output: Option<&Path>, text: &str) -> Result<(), CliError> {
+    match output {
+        Some(path) => std::fs::write(path, text).map_err(|err| CliError::Io(path.to_path_buf(), err)),
+        None => {
+            (std::io::stdout().write_all(text.as_bytes())).map_err(|err| CliError::Io(PathBuf::from("stdout"), err))
+        }
+    }
+}
+
+/// Prints solutions as soon as they are found, so they are never all in memory, returns their number
+fn write_solutions(solver: PuzzleSolver) -> Result<usize, CliError> {
+    let mut stdout = std::io::stdout().lock();
+    let mut found = 0;
+    let mut result = Ok(());
+    solver.for_each_solution(|dot_path, line_path| {
+        let solution = Solution {
+            dot_path: dot_path.to_vec(),
+            line_path: line_path.to_vec(),
+        };
+        result = (ron::to_string(&solution).map_err(|err| CliError::Failed(err.to_string())))
+            .and_then(|text| writeln!(stdout, "{text}").map_err(|err| CliError::Io(PathBuf::from("stdout"), err)));
+        found += 1;
+        if result.is_ok() {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    });
+    result.map(|()| found)
+}
+
+/// Path must go along lines from start dot without visiting dot twice, rules are checked separately
+fn validate_solution(puzzle: &Puzzle, solution: &Solution) -> Result<(), String> {
+    let dot_path = &solution.dot_path;
+    let Some(start_dot) = dot_path.first() else {
+        return Err("path is empty".to_owned());
+    };
+    if let Some(dot) = dot_path.iter().find(|dot| dot.0 as usize >= puzzle.dots.len()) {
+        return Err(format!("there is no dot {}", dot.0));
+    }
+    if !puzzle.start_dots.contains(start_dot) {
+        return Err(format!("dot {} is not a start", start_dot.0));
+    }
+    if solution.line_path.len() + 1 != dot_path.len() {
+        return Err("path needs one line less than dots".to_owned());
+    }
+    for (dots, line) in dot_path.windows(2).zip(&solution.line_path) {
+        if puzzle.find_line(dots[0], dots[1]) != Some(*line) {
+            return Err(format!(
+                "dots {} and {} aren't connected by line {line:?}",
+                dots[0].0, dots[1].0
+            ));
+        }
+    }
+    let mut visited = vec![false; puzzle.dots.len()];
+    for dot in dot_path {
+        if std::mem::replace(&mut visited[dot.0 as usize], true) {
+            return Err(format!("path visits dot {} twice", dot.0));
+        }
+    }
+    Ok(())
+}
+
+/// Runs every command except `Gui`, which needs window
+pub fn run(command: Command) -> Result<(), CliError> {
+    match command {
+        Command::Gui { .. } => unreachable!("gui is started by main"),
+        Command::Help => println!("{USAGE}"),
+        Command::Check { puzzle, solution } => {
+            let puzzle = load_puzzle(&puzzle)?;
+            let text = std::fs::read_to_string(&solution).map_err(|err| CliError::Io(solution.clone(), err))?;
+            let solution: Solution = ron::from_str(&text).map_err(|err| CliError::Parse(solution, err.to_string()))?;
+            validate_solution(&puzzle, &solution).map_err(|err| CliError::Failed(format!("wrong path: {err}")))?;
+            let errors = find_errors(&puzzle, &solution.dot_path, &solution.line_path);
+            if !errors.is_empty() {
+                let errors: Vec<String> = errors.iter().map(|err| format!("{err:?}")).collect();
+                return Err(CliError::Failed(format!("incorrect: {}", errors.join(", "))));
+            }
+            println!("correct");
+        }
+        Command::Solve { puzzle, count, limit } => {
+            let puzzle = load_puzzle(&puzzle)?;
+            let solver = PuzzleSolver::new(&puzzle, limit);
+            let found = if count {
+                let found = solver.count();
+                write_output(None, &format!("{found}\n"))?;
+                found
+            } else {
+                write_solutions(solver)?
+            };
+            if found == 0 {
+                return Err(CliError::Failed("puzzle has no solutions".to_owned()));
+            }
+        }
+        Command::Generate { seed, size, output } => {
+            let seed = seed.unwrap_or_else(|| rand::rng().random());
+            eprintln!("seed {seed}");
+            let puzzle = generate_puzzle(&mut StdRng::seed_from_u64(seed), size.0, size.1);
+            let text = puzzle.to_ron().map_err(|err| CliError::Failed(err.to_string()))?;
+            write_output(output.as_deref(), &text)?;
+        }
+        Command::Render { puzzle, output } => {
+            let puzzle = load_puzzle(&puzzle)?;
+            write_output(output.as_deref(), &render_svg(&puzzle, SVG_SIZE))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let command = parse_args(&args("generate --size 4x3 -o out.ron --seed 5")).unwrap();
+        assert!(matches!(
+            command,
+            Command::Generate { seed: Some(5), size: (4, 3), output: Some(path) } if path == Path::new("out.ron")
+        ));
+        let command = parse_args(&args("solve p.ron --count")).unwrap();
+        assert!(matches!(
+            command,
+            Command::Solve {
+                count: true,
+                limit: None,
+                ..
+            }
+        ));
+        let command = parse_args(&args("solve p.ron --limit 3")).unwrap();
+        assert!(matches!(
+            command,
+            Command::Solve {
+                count: false,
+                limit: Some(3),
+                ..
+            }
+        ));
+        assert!(matches!(parse_args(&[]).unwrap(), Command::Gui { path: None, .. }));
+
+        for wrong in [
+            "solve",
+            "solve p.ron --seed 1",
+            "solve p.ron --limit 0",
+            "solve p.ron --limit many",
+            "generate --size 1x5",
+            "generate --size 300x300",
+            "generate --size 5x13",
+            "generate --seed",
+            "fly",
+        ] {
+            let err = parse_args(&args(wrong)).err().unwrap();
+            assert_eq!(2, err.exit_code(), "{wrong}");
+        }
+    }
+
+    #[test]
+    fn test_validate_solution() {
+        let puzzle = Puzzle::default();
+        let solution = find_solutions(&puzzle, Some(1)).remove(0);
+        assert_eq!(Ok(()), validate_solution(&puzzle, &solution));
+
+        let mut jump = solution.clone();
+        jump.dot_path[1] = DotIndex(12);
+        assert!(validate_solution(&puzzle, &jump).is_err());
+        let mut short = solution.clone();
+        short.line_path.pop();
+        assert!(validate_solution(&puzzle, &short).is_err());
+        let empty = Solution {
+            dot_path: Vec::new(),
+            line_path: Vec::new(),
+        };
+        assert!(validate_solution(&puzzle, &empty).is_err());
+    }
+}
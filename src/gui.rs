@@ -2,7 +2,12 @@ mod common;
 use common::EguiDrawer;
 
 mod editor;
+mod file_browser;
+mod input;
+mod shell;
 mod solver;
+mod validation;
 
 pub use editor::EditorApp;
+pub use shell::{AppShell, ShellMode};
 pub use solver::SolverApp;
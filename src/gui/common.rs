@@ -1,68 +1,49 @@
 use crate::puzzle_logic::*;
 use eframe::egui::{self, Color32, Pos2, Rect, Vec2};
+use std::collections::HashMap;
 
 const START_DOT_SCALE: f32 = 3.0;
 const PANE_SCALE: f32 = 2.0;
+const EDGE_CLASS_SCALE: f32 = 0.4;
+const HINT_WRONG_COLOR: Color32 = Color32::from_rgba_premultiplied(200, 0, 0, 200);
+const REGION_ALPHA: f32 = 0.35;
+const REGION_COLORS: [Color32; 6] = [
+    Color32::from_rgb(230, 25, 75),
+    Color32::from_rgb(60, 180, 75),
+    Color32::from_rgb(0, 130, 200),
+    Color32::from_rgb(245, 130, 48),
+    Color32::from_rgb(145, 30, 180),
+    Color32::from_rgb(70, 240, 240),
+];
 
 pub struct EguiDrawer {
-    now_pos: Option<Pos2>,
-    last_pos: Option<Pos2>,
-    delta_pos: Option<Vec2>,
-    clicked: bool,
     draw_rect: Rect,
 }
 
 impl Default for EguiDrawer {
     fn default() -> Self {
-        Self {
-            now_pos: None,
-            last_pos: None,
-            delta_pos: None,
-            clicked: false,
-            draw_rect: Rect::ZERO,
-        }
+        Self { draw_rect: Rect::ZERO }
     }
 }
 
 impl EguiDrawer {
-    pub fn update(&mut self, ctx: &egui::Context) {
+    /// Fits puzzle square into `rect`, which is the area left by the panels
+    pub fn update(&mut self, rect: Rect) {
         self.draw_rect = {
-            let screen_rect = ctx.screen_rect();
             let size = {
-                let Vec2 { x, y } = screen_rect.size();
+                let Vec2 { x, y } = rect.size();
                 x.min(y)
             };
-            let center = screen_rect.center();
+            let center = rect.center();
             Rect::from_center_size(center, Vec2::splat(size))
         };
-
-        self.last_pos = self.now_pos;
-        self.now_pos = {
-            let pos = ctx.input(|i| i.pointer.hover_pos());
-            if let Some(Pos2 { x, y }) = pos {
-                let x = (x - self.draw_rect.left()) / self.draw_rect.width();
-                let y = 1.0 - (y - self.draw_rect.top()) / self.draw_rect.height();
-                Some(Pos2 { x, y })
-            } else {
-                None
-            }
-        };
-        if self.now_pos.is_some() && self.last_pos.is_some() {
-            self.delta_pos = Some(self.now_pos.unwrap() - self.last_pos.unwrap());
-        } else {
-            self.delta_pos = None;
-        }
-        self.clicked = ctx.input(|i| i.pointer.button_pressed(egui::PointerButton::Primary));
     }
 
-    pub fn get_mouse_pos(&self) -> Option<Pos2> {
-        self.now_pos
-    }
-    pub fn get_mouse_delta(&self) -> Option<Vec2> {
-        self.delta_pos
-    }
-    pub fn clicked(&self) -> bool {
-        self.clicked
+    /// Converts screen position to puzzle coords
+    pub fn get_dot(&self, pos: Pos2) -> Dot {
+        let x = (pos.x - self.draw_rect.left()) / self.draw_rect.width();
+        let y = 1.0 - (pos.y - self.draw_rect.top()) / self.draw_rect.height();
+        Dot { x, y }
     }
 }
 impl EguiDrawer {
@@ -131,20 +112,84 @@ impl EguiDrawer {
         let color = puzzle.solution_color;
         let width = puzzle.line_width * self.draw_rect.width();
 
-        if solution_manager.is_drawing_solution() {
-            let start_dot = solution_manager.get_start_dot_dot_draw();
+        if let (Ok(start_dot), Ok(lines)) = (
+            solution_manager.get_start_dot_dot_draw(),
+            solution_manager.get_lines_to_draw(),
+        ) {
             self.draw_dot(ui, start_dot, width * START_DOT_SCALE, color);
 
-            for &(dot1, dot2) in &solution_manager.get_lines_to_draw() {
+            for &(dot1, dot2) in &lines {
                 self.draw_dot(ui, dot1, width, color);
                 self.draw_dot(ui, dot2, width, color);
                 self.draw_line(ui, (dot1, dot2), width, color);
             }
         }
     }
+    /// Path of solution found by solver, without solution manager
+    pub fn draw_solution(&self, ui: &mut egui::Ui, puzzle: &Puzzle, solution: &Solution) {
+        let color = puzzle.solution_color;
+        let width = self.get_line_width(puzzle);
+        let get_dot = |dot: DotIndex| puzzle.dots[dot.0 as usize];
+
+        if let Some(&start_dot) = solution.dot_path.first() {
+            self.draw_dot(ui, get_dot(start_dot), width * START_DOT_SCALE, color);
+        }
+        for line in &solution.line_path {
+            let (dot1, dot2) = (get_dot(line.0), get_dot(line.1));
+            self.draw_dot(ui, dot1, width, color);
+            self.draw_dot(ui, dot2, width, color);
+            self.draw_line(ui, (dot1, dot2), width, color);
+        }
+    }
+    /// Tints every region, which path splits panel into, with its own color
+    pub fn draw_regions(&self, ui: &mut egui::Ui, puzzle: &Puzzle, line_path: &[LineIndex]) {
+        let polygons = puzzle.pane_polygons();
+        for (i, component) in find_components(puzzle, line_path).iter().enumerate() {
+            let color = REGION_COLORS[i % REGION_COLORS.len()].gamma_multiply(REGION_ALPHA);
+            for pane in component {
+                let points = (polygons[pane.0 as usize].iter())
+                    .map(|&dot| self.get_point(dot))
+                    .collect();
+                let shape = egui::Shape::convex_polygon(points, color, egui::Stroke::NONE);
+                ui.painter().add(shape);
+            }
+        }
+    }
+    pub fn draw_hint(&self, ui: &mut egui::Ui, puzzle: &Puzzle, solution_manager: &PuzzleSolutionManager, hint: Hint) {
+        let width = puzzle.line_width * self.draw_rect.width();
+        let get_dot = |dot: DotIndex| puzzle.dots[dot.0 as usize];
+        match hint {
+            Hint::NextLine(line) => {
+                let color = puzzle.solution_color.gamma_multiply(0.4);
+                self.draw_line(ui, (get_dot(line.0), get_dot(line.1)), width, color);
+            }
+            Hint::WrongTurn { correct_dots } => {
+                let dot_path = solution_manager.dot_path();
+                let wrong_dot = match correct_dots {
+                    0 => dot_path[0],
+                    _ => dot_path[correct_dots - 1],
+                };
+                self.draw_dot(ui, get_dot(wrong_dot), width * START_DOT_SCALE, HINT_WRONG_COLOR);
+            }
+            Hint::Complete | Hint::NoSolution => {}
+        }
+    }
+    pub fn draw_edge_classes(&self, ui: &mut egui::Ui, puzzle: &Puzzle, edge_classes: &HashMap<LineIndex, EdgeClass>) {
+        let width = puzzle.line_width * self.draw_rect.width() * EDGE_CLASS_SCALE;
+        let get_dot = |dot: DotIndex| puzzle.dots[dot.0 as usize];
+        for (line, class) in edge_classes {
+            let color = match class {
+                EdgeClass::Forced => Color32::from_rgb(40, 200, 40),
+                EdgeClass::Unused => Color32::from_rgb(200, 40, 40),
+                EdgeClass::Varies => Color32::from_rgb(230, 230, 230),
+            };
+            self.draw_line(ui, (get_dot(line.0), get_dot(line.1)), width, color);
+        }
+    }
+    #[allow(dead_code)]
     pub fn draw_debug_pane_connections(&self, ui: &mut egui::Ui, puzzle: &Puzzle, solution_manager: &PuzzleSolutionManager) {
         for (pane_index, vec) in puzzle.pane_nears.iter().enumerate() {
-            let dot1 = puzzle.panes[pane_index as usize];
+            let dot1 = puzzle.panes[pane_index];
 
             for (line_index, pane_index2) in vec {
                 let dot2 = puzzle.panes[pane_index2.0 as usize];
@@ -1,6 +1,11 @@
 use super::EguiDrawer;
+use super::file_browser::{BrowserMode, BrowserResult, FileBrowser};
+use super::input::InputFrame;
+use super::validation::BackgroundValidator;
 use crate::puzzle_logic::*;
-use eframe::egui::{self, Color32, Frame, Pos2, Rect, Response, Stroke, Vec2};
+use eframe::egui::{self, Align2, Color32, FontId, Frame, Rect, Response, Stroke, Vec2};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 const BUTTON_SIZE: f32 = 60.0;
 const SIDE_PANEL_SIZE: f32 = 80.0;
@@ -8,6 +13,11 @@ const SIDE_PANEL_PADDING: f32 = (SIDE_PANEL_SIZE - BUTTON_SIZE) / 2.0;
 
 const SIDE_COLOR: Color32 = Color32::from_rgb(40, 40, 40);
 
+const EDIT_HISTORY_LIMIT: usize = 200;
+const DROPPED_COLOR: Color32 = Color32::from_rgb(220, 40, 40);
+const NEW_GRID_SIZE: usize = 5;
+const LINE_WIDTH_RANGE: std::ops::RangeInclusive<f32> = 0.01..=0.08;
+
 enum SelectedObject {
     None,
     Dot(DotIndex),
@@ -25,56 +35,314 @@ enum SelectedComplexity {
     // Triangle,
 }
 
-pub struct EditorApp<'a> {
-    puzzle: &'a mut Puzzle,
+/// Tools changing the graph itself, used instead of complexity when selected
+#[derive(Clone, Copy, PartialEq, Eq, enum_iterator::Sequence)]
+enum TopologyTool {
+    AddDot,
+    MoveDot,
+    /// Click two dots to connect them, click line to remove it
+    Connect,
+    Delete,
+    /// Toggles start on dot
+    Start,
+    /// Drag from border dot adds end in drag direction, click on end removes it
+    End,
+}
+
+/// File actions which lose unsaved changes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DiscardAction {
+    New,
+    Open,
+    Close,
+}
+
+impl TopologyTool {
+    fn label(&self) -> &'static str {
+        match self {
+            TopologyTool::AddDot => "Dot",
+            TopologyTool::MoveDot => "Move",
+            TopologyTool::Connect => "Line",
+            TopologyTool::Delete => "Delete",
+            TopologyTool::Start => "Start",
+            TopologyTool::End => "End",
+        }
+    }
+}
+
+pub struct EditorApp {
+    puzzle: Puzzle,
     drawer: EguiDrawer,
+    /// Area of central panel from last frame, pointer outside of it belongs to other panels
+    canvas_rect: Rect,
 
     selected_object: SelectedObject,
     selected_complexity: SelectedComplexity,
     selected_color: ComplexityColor,
+    selected_tool: Option<TopologyTool>,
+    /// Dragged dot and puzzle before the drag
+    drag: Option<(DotIndex, Puzzle)>,
+    connect_from: Option<DotIndex>,
+    end_from: Option<DotIndex>,
+    message: Option<String>,
+    /// Grid size chosen in resize controls, follows puzzle until changed
+    resize_to: Option<(usize, usize)>,
+    grid_size: Option<(usize, usize)>,
+
+    edge_classes: HashMap<LineIndex, EdgeClass>,
+    show_edge_classes: bool,
+    validator: BackgroundValidator,
+    /// Index of solution drawn over the puzzle
+    shown_solution: Option<usize>,
+    /// Puzzle before style change, slider drag or open color picker is one edit
+    style_edit: Option<Puzzle>,
+
+    history: EditHistory,
+
+    /// File of the puzzle, `None` until saved
+    path: Option<PathBuf>,
+    browser: Option<FileBrowser>,
+    /// Action waiting for confirmation to discard unsaved changes
+    pending_discard: Option<DiscardAction>,
+    close_confirmed: bool,
 }
 
-impl eframe::App for EditorApp<'_> {
+impl eframe::App for EditorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.drawer.update(ctx);
+        let input = InputFrame::read(ctx, &self.drawer);
 
-        if let Some(pos) = self.drawer.get_mouse_pos() {
-            self.update_selection(pos);
+        if ctx.input(|i| i.viewport().close_requested()) && self.is_dirty() && !self.close_confirmed {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.pending_discard = Some(DiscardAction::Close);
         }
-        if self.drawer.clicked() {
-            self.click();
+        if self.browser.is_none() && self.pending_discard.is_none() {
+            self.update_input(ctx, &input);
+        }
+        if self.validator.poll()
+            && let Some(report) = self.validator.report()
+        {
+            // classes of part of solutions would be misleading
+            if !report.is_capped {
+                self.edge_classes = analyze_edges(&self.puzzle, &report.solutions);
+            }
+            let count = report.solutions.len();
+            self.shown_solution = (self.shown_solution).filter(|_| count > 0).map(|i| i.min(count - 1));
         }
 
+        let grid_size = self.puzzle.grid_size();
+        if grid_size != self.grid_size {
+            self.grid_size = grid_size;
+            self.resize_to = grid_size;
+        }
+        let mut resize_preview = (self.resize_to)
+            .filter(|size| Some(*size) != grid_size)
+            .and_then(|(width, height)| self.puzzle.resize_grid(width, height));
+
+        // side panels go first, central panel gets what they leave
+        self.render_ui(ctx);
+        self.render_inspector_panel(ctx);
+        self.render_file_controls(ctx);
+        self.render_resize_controls(ctx, &mut resize_preview);
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.painter().rect_filled(
-                ctx.screen_rect(),
-                egui::CornerRadius::ZERO,
-                self.puzzle.background_color,
-            );
+            self.canvas_rect = ui.max_rect();
+            self.drawer.update(self.canvas_rect);
+            ui.painter()
+                .rect_filled(ui.max_rect(), egui::CornerRadius::ZERO, self.puzzle.background_color);
             self.render_puzzle(ui);
+            if let Some(resize) = &resize_preview {
+                self.render_resize_preview(ui, resize);
+            }
+            if let Some(message) = &self.message {
+                ui.label(message);
+            }
         });
-        self.render_ui(ctx);
+        self.render_dialogs(ctx);
+        self.update_style_edit(ctx);
         ctx.request_repaint();
     }
 }
 
 // Logic
-impl<'a> EditorApp<'a> {
-    pub fn new(_cc: &eframe::CreationContext<'_>, puzzle: &'a mut Puzzle) -> Self {
-        Self {
+impl EditorApp {
+    /// `path` is where the puzzle is saved, it may not exist yet
+    pub fn new(_cc: &eframe::CreationContext<'_>, puzzle: Puzzle, path: Option<PathBuf>) -> Self {
+        let mut editor = Self {
             puzzle,
             drawer: EguiDrawer::default(),
+            canvas_rect: Rect::NOTHING,
             selected_object: SelectedObject::None,
             selected_complexity: SelectedComplexity::Hexagon,
             selected_color: ComplexityColor::Black,
+            selected_tool: None,
+            drag: None,
+            connect_from: None,
+            end_from: None,
+            message: None,
+            resize_to: None,
+            grid_size: None,
+            edge_classes: HashMap::new(),
+            show_edge_classes: true,
+            validator: BackgroundValidator::default(),
+            shown_solution: None,
+            style_edit: None,
+            history: EditHistory::new(EDIT_HISTORY_LIMIT),
+            path,
+            browser: None,
+            pending_discard: None,
+            close_confirmed: false,
+        };
+        editor.update_analysis();
+        editor
+    }
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+    /// Puzzle has changes which aren't saved to file
+    pub fn is_dirty(&self) -> bool {
+        self.history.is_dirty()
+    }
+    fn update_input(&mut self, ctx: &egui::Context, input: &InputFrame) {
+        let pointer_pos = input.pointer_pos.filter(|_| self.is_pointer_on_canvas(ctx));
+        match pointer_pos {
+            Some(pos) => self.update_selection(pos),
+            None => self.selected_object = SelectedObject::None,
+        }
+        if input.primary_pressed
+            && let Some(pos) = pointer_pos
+        {
+            match self.selected_tool {
+                None => self.click(),
+                Some(tool) => self.topology_click(tool, pos),
+            }
+            self.update_analysis();
+        }
+        self.update_drag(input);
+        self.update_end_drag(input);
+        if input.key_pressed(egui::Key::A) {
+            self.show_edge_classes = !self.show_edge_classes;
+        }
+        if input.command_down && input.key_pressed(egui::Key::Z) {
+            let changed = if input.shift_down {
+                self.history.redo(&mut self.puzzle)
+            } else {
+                self.history.undo(&mut self.puzzle)
+            };
+            if changed {
+                self.reset_interaction();
+                self.update_analysis();
+            }
+        }
+        if input.command_down && input.key_pressed(egui::Key::N) {
+            self.request_discard(ctx, DiscardAction::New);
+        }
+        if input.command_down && input.key_pressed(egui::Key::O) {
+            self.request_discard(ctx, DiscardAction::Open);
+        }
+        if input.command_down && input.key_pressed(egui::Key::S) {
+            if input.shift_down {
+                self.save_as();
+            } else {
+                self.save();
+            }
         }
     }
+
+    fn request_discard(&mut self, ctx: &egui::Context, action: DiscardAction) {
+        if self.is_dirty() {
+            self.pending_discard = Some(action);
+        } else {
+            self.discard(ctx, action);
+        }
+    }
+    fn discard(&mut self, ctx: &egui::Context, action: DiscardAction) {
+        match action {
+            DiscardAction::New => self.set_puzzle(Puzzle::playable_grid(NEW_GRID_SIZE, NEW_GRID_SIZE), None),
+            DiscardAction::Open => self.browser = Some(FileBrowser::new(BrowserMode::Open, self.path.as_deref())),
+            DiscardAction::Close => {
+                self.close_confirmed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+    /// Replaces puzzle without undo, like new or opened file
+    fn set_puzzle(&mut self, puzzle: Puzzle, path: Option<PathBuf>) {
+        self.puzzle = puzzle;
+        self.path = path;
+        self.history.clear();
+        self.reset_interaction();
+        self.message = None;
+        self.update_analysis();
+    }
+    /// Panels, windows and popups over the canvas take the pointer
+    fn is_pointer_on_canvas(&self, ctx: &egui::Context) -> bool {
+        let Some(pos) = ctx.pointer_hover_pos() else {
+            return false;
+        };
+        let layer = ctx.layer_id_at(pos);
+        self.canvas_rect.contains(pos) && layer.is_none_or(|layer| layer.order == egui::Order::Background)
+    }
+    /// Unfinished clicks and drags refer to dots of old puzzle, they are dropped when puzzle is replaced
+    fn reset_interaction(&mut self) {
+        self.selected_object = SelectedObject::None;
+        self.drag = None;
+        self.connect_from = None;
+        self.end_from = None;
+    }
+    fn open(&mut self, path: PathBuf) {
+        let puzzle = (std::fs::read_to_string(&path).map_err(|err| err.to_string()))
+            .and_then(|text| Puzzle::from_ron(&text).map_err(|err| err.to_string()))
+            .and_then(|puzzle| {
+                let broken = validate_puzzle(&puzzle).into_iter().find(ValidationWarning::is_broken);
+                broken.map_or(Ok(puzzle), |warning| Err(warning.to_string()))
+            });
+        match puzzle {
+            Ok(puzzle) => self.set_puzzle(puzzle, Some(path)),
+            Err(err) => self.message = Some(format!("Can't open {}: {err}", path.display())),
+        }
+    }
+    fn save(&mut self) {
+        match self.path.clone() {
+            Some(path) => self.save_to(path),
+            None => self.save_as(),
+        }
+    }
+    fn save_as(&mut self) {
+        self.browser = Some(FileBrowser::new(BrowserMode::Save, self.path.as_deref()));
+    }
+    fn save_to(&mut self, path: PathBuf) {
+        let saved = (self.puzzle.to_ron().map_err(|err| err.to_string()))
+            .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+        match saved {
+            Ok(()) => {
+                self.history.mark_saved();
+                self.message = Some(format!("Saved to {}", path.display()));
+                self.path = Some(path);
+            }
+            Err(err) => self.message = Some(format!("Can't save {}: {err}", path.display())),
+        }
+    }
+    /// Edge classes are shown again when background validation finishes
+    fn update_analysis(&mut self) {
+        self.edge_classes.clear();
+        self.validator.start(&self.puzzle);
+    }
+    /// Records style change when user stops dragging slider or closes color picker
+    fn update_style_edit(&mut self, ctx: &egui::Context) {
+        let is_editing = ctx.is_using_pointer() || ctx.memory(|memory| memory.any_popup_open());
+        if !is_editing && let Some(old) = self.style_edit.take() {
+            self.history.record(PuzzleEdit::replace(&old, self.puzzle.clone()));
+        }
+    }
+    /// Solutions of old puzzle aren't shown while new ones are searched
+    fn shown_solution(&self) -> Option<&Solution> {
+        let report = self.validator.report().filter(|_| !self.validator.is_running())?;
+        report.solutions.get(self.shown_solution?)
+    }
     fn get_dot(&self, dot_index: DotIndex) -> Dot {
         self.puzzle.dots[dot_index.0 as usize]
     }
 
-    fn update_selection(&mut self, pos: Pos2) {
-        let mouse_dot = Dot { x: pos.x, y: pos.y };
+    fn update_selection(&mut self, mouse_dot: Dot) {
         // Dot
         let dot_radius = self.puzzle.line_width / 2.0;
         for (i, &dot) in self.puzzle.dots.iter().enumerate() {
@@ -121,51 +389,142 @@ impl<'a> EditorApp<'a> {
         self.selected_object = SelectedObject::None;
     }
     fn click(&mut self) {
-        match self.selected_complexity {
+        let puzzle = &self.puzzle;
+        // symbol is removed if there is one, otherwise selected one is placed
+        let edit = match self.selected_complexity {
             SelectedComplexity::Hexagon => match self.selected_object {
-                SelectedObject::None => {}
+                SelectedObject::None => None,
                 SelectedObject::Dot(key) => {
-                    let map = &mut self.puzzle.dot_complexity;
-                    if map.remove(&key).is_none() {
-                        map.insert(key, DotComplexity::BlackHexagon);
-                    }
+                    let new = (!puzzle.dot_complexity.contains_key(&key)).then_some(DotComplexity::BlackHexagon);
+                    Some(PuzzleEdit::set_dot_complexity(puzzle, key, new))
                 }
                 SelectedObject::Line(key) => {
-                    let map = &mut self.puzzle.line_complexity;
-                    if map.remove(&key).is_none() {
-                        map.insert(key, LineComplexity::BlackHexagon);
-                    }
+                    let new = (!puzzle.line_complexity.contains_key(&key)).then_some(LineComplexity::BlackHexagon);
+                    Some(PuzzleEdit::set_line_complexity(puzzle, key, new))
                 }
-                SelectedObject::Pane(_) => {}
+                SelectedObject::Pane(_) => None,
             },
             SelectedComplexity::LineBreak => match self.selected_object {
-                SelectedObject::None => {}
-                SelectedObject::Dot(_) => {}
+                SelectedObject::None => None,
+                SelectedObject::Dot(_) => None,
                 SelectedObject::Line(key) => {
-                    let map = &mut self.puzzle.line_complexity;
-                    if map.remove(&key).is_none() {
-                        map.insert(key, LineComplexity::LineBreak);
-                    }
+                    let new = (!puzzle.line_complexity.contains_key(&key)).then_some(LineComplexity::LineBreak);
+                    Some(PuzzleEdit::set_line_complexity(puzzle, key, new))
                 }
-                SelectedObject::Pane(_) => {}
+                SelectedObject::Pane(_) => None,
             },
             SelectedComplexity::Square => match self.selected_object {
-                SelectedObject::None => {}
-                SelectedObject::Dot(_) => {}
-                SelectedObject::Line(_) => {}
+                SelectedObject::None => None,
+                SelectedObject::Dot(_) => None,
+                SelectedObject::Line(_) => None,
                 SelectedObject::Pane(key) => {
-                    let map = &mut self.puzzle.pane_complexity;
-                    if map.remove(&key).is_none() {
-                        map.insert(key, PaneComplexity::Square(self.selected_color));
-                    }
+                    let new = (!puzzle.pane_complexity.contains_key(&key))
+                        .then_some(PaneComplexity::Square(self.selected_color));
+                    Some(PuzzleEdit::set_pane_complexity(puzzle, key, new))
+                }
+            },
+        };
+        if let Some(edit) = edit {
+            self.history.apply(&mut self.puzzle, edit);
+        }
+    }
+    fn topology_click(&mut self, tool: TopologyTool, pos: Dot) {
+        let mut puzzle = self.puzzle.clone();
+        let result = match (tool, &self.selected_object) {
+            (TopologyTool::AddDot, SelectedObject::None | SelectedObject::Pane(_)) => {
+                puzzle.add_dot(pos);
+                Ok(())
+            }
+            (TopologyTool::MoveDot, SelectedObject::Dot(dot)) => {
+                self.drag = Some((*dot, self.puzzle.clone()));
+                return;
+            }
+            (TopologyTool::Connect, SelectedObject::Dot(dot)) => match self.connect_from.take() {
+                None => {
+                    self.connect_from = Some(*dot);
+                    return;
                 }
+                Some(from) => puzzle.add_line(from, *dot).map(|_| ()),
             },
+            (TopologyTool::Connect | TopologyTool::Delete, SelectedObject::Line(line)) => puzzle.remove_line(*line),
+            (TopologyTool::Delete, SelectedObject::Dot(dot)) => puzzle.remove_dot(*dot),
+            (TopologyTool::Start, SelectedObject::Dot(dot)) => puzzle.toggle_start_dot(*dot).map(|_| ()),
+            (TopologyTool::End, SelectedObject::Dot(dot)) => {
+                if !puzzle.end_dots.contains(dot) {
+                    self.end_from = Some(*dot);
+                    return;
+                }
+                puzzle.remove_end_stub(*dot)
+            }
+            _ => return,
+        };
+        match result {
+            Ok(()) => {
+                let edit = PuzzleEdit::replace(&self.puzzle, puzzle);
+                self.history.apply(&mut self.puzzle, edit);
+                self.message = None;
+            }
+            Err(err) => self.message = Some(err.to_string()),
+        }
+    }
+    /// End is added on release, in pointer direction snapped to 45 degrees
+    fn update_end_drag(&mut self, input: &InputFrame) {
+        if input.primary_down {
+            return;
+        }
+        let (Some(dot), Some(pos)) = (self.end_from.take(), input.pointer_pos) else {
+            return;
+        };
+        let mut direction = pos - self.get_dot(dot);
+        if direction.length() < self.puzzle.line_width {
+            // short click, end goes away from neighbors
+            let neighbors: Vec<Dot> = (self.puzzle.lines.iter())
+                .filter(|line| line.contains(dot))
+                .map(|line| self.get_dot(line.other(dot)))
+                .collect();
+            let center = neighbors.iter().fold(Dot::ZERO, |sum, x| sum + *x);
+            direction = self.get_dot(dot) - center.scale(1.0 / neighbors.len().max(1) as f32);
+        }
+        if direction.length2() == 0.0 {
+            return;
+        }
+        let step = std::f32::consts::FRAC_PI_4;
+        let angle = (direction.y.atan2(direction.x) / step).round() * step;
+        let mut puzzle = self.puzzle.clone();
+        match puzzle.add_end_stub(dot, Dot::new(angle.cos(), angle.sin())) {
+            Ok(_) => {
+                let edit = PuzzleEdit::replace(&self.puzzle, puzzle);
+                self.history.apply(&mut self.puzzle, edit);
+                self.message = None;
+                self.update_analysis();
+            }
+            Err(err) => self.message = Some(err.to_string()),
+        }
+    }
+    /// Dot follows pointer while button is down, whole drag is one history entry
+    fn update_drag(&mut self, input: &InputFrame) {
+        let Some((dot, _)) = self.drag else {
+            return;
+        };
+        if input.primary_down {
+            if let Some(pos) = input.pointer_pos {
+                // crossing positions are skipped, dot waits at the last good one
+                let _ = self.puzzle.move_dot(dot, pos);
+            }
+            return;
+        }
+        let Some((_, old)) = self.drag.take() else {
+            return;
+        };
+        if old.dots != self.puzzle.dots {
+            self.history.record(PuzzleEdit::replace(&old, self.puzzle.clone()));
+            self.update_analysis();
         }
     }
 }
 
 // render
-impl EditorApp<'_> {
+impl EditorApp {
     fn render_ui(&mut self, ctx: &egui::Context) {
         egui::SidePanel::right("Color")
             .resizable(false)
@@ -188,10 +547,204 @@ impl EditorApp<'_> {
                     enum_iterator::all::<SelectedComplexity>().for_each(|complexity| {
                         ui.add_space(SIDE_PANEL_PADDING);
                         self.complexity_button(ui, complexity);
-                    })
+                    });
+                    enum_iterator::all::<TopologyTool>().for_each(|tool| {
+                        ui.add_space(SIDE_PANEL_PADDING);
+                        self.topology_button(ui, tool);
+                    });
                 })
             });
     }
+    /// Validation and style share one panel, each section can be collapsed
+    fn render_inspector_panel(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("Inspector").resizable(false).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::CollapsingHeader::new("Validation")
+                    .default_open(true)
+                    .show(ui, |ui| self.render_validation(ui));
+                egui::CollapsingHeader::new("Style")
+                    .default_open(true)
+                    .show(ui, |ui| self.render_style(ui));
+            });
+        });
+    }
+    fn render_validation(&mut self, ui: &mut egui::Ui) {
+        let Some(report) = self.validator.report() else {
+            ui.label("Checking...");
+            return;
+        };
+        if report.solutions.is_empty() {
+            ui.colored_label(DROPPED_COLOR, "Unsolvable");
+        } else {
+            ui.label("Solvable");
+        }
+        let capped_mark = if report.is_capped { "+" } else { "" };
+        ui.label(format!("Solutions: {}{capped_mark}", report.solutions.len()));
+        let count = report.solutions.len();
+        if count > 0 {
+            ui.horizontal(|ui| {
+                let mut is_shown = self.shown_solution.is_some();
+                if ui.checkbox(&mut is_shown, "Show").changed() {
+                    self.shown_solution = is_shown.then_some(0);
+                }
+                if let Some(i) = &mut self.shown_solution {
+                    if ui.button("<").clicked() {
+                        *i = (*i + count - 1) % count;
+                    }
+                    ui.label(format!("{}/{count}", *i + 1));
+                    if ui.button(">").clicked() {
+                        *i = (*i + 1) % count;
+                    }
+                }
+            });
+        }
+        ui.label(format!("Solver time: {:.1} ms", report.elapsed.as_secs_f64() * 1000.0));
+        for warning in &report.warnings {
+            ui.colored_label(Color32::YELLOW, warning.to_string());
+        }
+        if self.validator.is_running() {
+            ui.label("Checking...");
+        }
+    }
+    fn render_style(&mut self, ui: &mut egui::Ui) {
+        let old_style = self.puzzle.style();
+        let mut style = old_style;
+        ui.label("Line width");
+        ui.add(egui::Slider::new(&mut style.line_width, LINE_WIDTH_RANGE));
+        let colors = [
+            ("Background", &mut style.background_color),
+            ("Lines", &mut style.puzzle_color),
+            ("Solution", &mut style.solution_color),
+        ];
+        for (name, color) in colors {
+            ui.horizontal(|ui| {
+                egui::color_picker::color_edit_button_srgba(ui, color, egui::color_picker::Alpha::Opaque);
+                ui.label(name);
+            });
+        }
+
+        ui.separator();
+        ui.label("Presets");
+        for (name, preset) in STYLE_PRESETS {
+            if ui.button(name).clicked() {
+                style = preset;
+            }
+        }
+        if style != old_style {
+            if self.style_edit.is_none() {
+                self.style_edit = Some(self.puzzle.clone());
+            }
+            self.puzzle.set_style(style);
+        }
+    }
+    fn render_file_controls(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("File").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("New").clicked() {
+                    self.request_discard(ctx, DiscardAction::New);
+                }
+                if ui.button("Open").clicked() {
+                    self.request_discard(ctx, DiscardAction::Open);
+                }
+                if ui.button("Save").clicked() {
+                    self.save();
+                }
+                if ui.button("Save as").clicked() {
+                    self.save_as();
+                }
+                let name = (self.path.as_ref().and_then(|path| path.file_name()))
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "untitled".to_owned());
+                let dirty_mark = if self.is_dirty() { "*" } else { "" };
+                ui.label(format!("{name}{dirty_mark}"));
+            });
+        });
+    }
+    fn render_dialogs(&mut self, ctx: &egui::Context) {
+        if let Some(browser) = &mut self.browser
+            && let Some(result) = browser.show(ctx)
+        {
+            let mode = browser.mode();
+            self.browser = None;
+            match (result, mode) {
+                (BrowserResult::Picked(path), BrowserMode::Open) => self.open(path),
+                (BrowserResult::Picked(path), BrowserMode::Save) => self.save_to(path),
+                (BrowserResult::Cancelled, _) => {}
+            }
+        }
+
+        let Some(action) = self.pending_discard else {
+            return;
+        };
+        let mut confirmed = None;
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Puzzle has unsaved changes, discard them?");
+                ui.horizontal(|ui| {
+                    if ui.button("Discard").clicked() {
+                        confirmed = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        confirmed = Some(false);
+                    }
+                });
+            });
+        if let Some(confirmed) = confirmed {
+            self.pending_discard = None;
+            if confirmed {
+                self.discard(ctx, action);
+            }
+        }
+    }
+    /// Applied resize is taken out of `preview`
+    fn render_resize_controls(&mut self, ctx: &egui::Context, preview: &mut Option<GridResize>) {
+        let Some((mut width, mut height)) = self.resize_to else {
+            return;
+        };
+        egui::TopBottomPanel::bottom("Grid").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Grid");
+                ui.add(egui::DragValue::new(&mut width).range(2..=MAX_GRID_SIZE));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut height).range(2..=MAX_GRID_SIZE));
+                let Some(resize) = preview.as_ref() else {
+                    return;
+                };
+                let dropped = resize.dropped_symbols.len();
+                let starts = resize.dropped_starts.len();
+                let ends = resize.dropped_ends.len();
+                ui.label(format!("drops {dropped} symbols, {starts} starts, {ends} ends"));
+                if ui.button("Resize").clicked()
+                    && let Some(resize) = preview.take()
+                {
+                    let edit = PuzzleEdit::replace(&self.puzzle, resize.puzzle);
+                    self.history.apply(&mut self.puzzle, edit);
+                    self.reset_interaction();
+                    self.update_analysis();
+                }
+            });
+        });
+        self.resize_to = Some((width, height));
+    }
+    /// Marks what resize would drop
+    fn render_resize_preview(&self, ui: &mut egui::Ui, resize: &GridResize) {
+        let width = self.drawer.get_line_width(&self.puzzle) * 1.5;
+        let color = DROPPED_COLOR.gamma_multiply(0.7);
+        let symbol_dots = resize.dropped_symbols.iter().map(|symbol| match *symbol {
+            Symbol::Dot(dot, _) => self.get_dot(dot),
+            Symbol::Line(line, _) => (self.get_dot(line.0) + self.get_dot(line.1)).scale(0.5),
+            Symbol::Pane(pane, _) => self.puzzle.panes[pane.0 as usize],
+        });
+        let dots = (resize.dropped_starts.iter())
+            .chain(&resize.dropped_ends)
+            .map(|dot| self.get_dot(*dot));
+        for dot in symbol_dots.chain(dots) {
+            self.drawer.draw_dot(ui, dot, width, color);
+        }
+    }
     fn reserve_button(&self, ui: &mut egui::Ui) -> (Rect, Response) {
         let size = Vec2::splat(BUTTON_SIZE);
         ui.allocate_exact_size(size, egui::Sense::click())
@@ -239,20 +792,48 @@ impl EditorApp<'_> {
                 self.drawer.draw_square(ui, pos, width, ComplexityColor::Black);
             }
         };
-        let selected = self.selected_complexity == complexity;
+        let selected = self.selected_tool.is_none() && self.selected_complexity == complexity;
         if selected {
             let stroke_color = Color32::from_gray(100);
             painter.rect_stroke(rect, 0.0, Stroke::new(2.0, stroke_color), egui::StrokeKind::Inside);
         }
         if response.clicked() {
-            self.selected_complexity = complexity
+            self.selected_complexity = complexity;
+            self.selected_tool = None;
+        }
+    }
+    fn topology_button(&mut self, ui: &mut egui::Ui, tool: TopologyTool) {
+        let (rect, response) = self.reserve_button(ui);
+        let painter = ui.painter_at(rect);
+        painter.text(rect.center(), Align2::CENTER_CENTER, tool.label(), FontId::proportional(16.0), Color32::WHITE);
+        if self.selected_tool == Some(tool) {
+            let stroke_color = Color32::from_gray(100);
+            painter.rect_stroke(rect, 0.0, Stroke::new(2.0, stroke_color), egui::StrokeKind::Inside);
+        }
+        if response.clicked() {
+            self.selected_tool = Some(tool);
+            self.connect_from = None;
         }
     }
 
     fn render_puzzle(&self, ui: &mut egui::Ui) {
-        self.drawer.draw_puzzle(ui, self.puzzle);
+        let shown_solution = self.shown_solution();
+        if let Some(solution) = shown_solution {
+            self.drawer.draw_regions(ui, &self.puzzle, &solution.line_path);
+        }
+        self.drawer.draw_puzzle(ui, &self.puzzle);
+        if self.show_edge_classes {
+            self.drawer.draw_edge_classes(ui, &self.puzzle, &self.edge_classes);
+        }
+        if let Some(solution) = shown_solution {
+            self.drawer.draw_solution(ui, &self.puzzle, solution);
+        }
 
-        let width = self.drawer.get_line_width(self.puzzle);
+        let width = self.drawer.get_line_width(&self.puzzle);
+        if let Some(dot_index) = self.connect_from {
+            let color = self.puzzle.solution_color.gamma_multiply(0.5);
+            self.drawer.draw_dot(ui, self.get_dot(dot_index), width * 1.5, color);
+        }
         let color = Color32::from_rgba_unmultiplied(255, 255, 255, 15);
         match self.selected_object {
             SelectedObject::None => {}
@@ -0,0 +1,177 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+pub const PUZZLE_EXTENSION: &str = "ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowserMode {
+    Open,
+    Save,
+}
+
+pub enum BrowserResult {
+    Picked(PathBuf),
+    Cancelled,
+}
+
+/// Window for picking puzzle file, shows directories and puzzle files of one directory
+pub struct FileBrowser {
+    mode: BrowserMode,
+    dir: PathBuf,
+    file_name: String,
+    /// Directories first, both sorted by name
+    entries: Vec<(String, bool)>,
+    error: Option<String>,
+}
+
+impl FileBrowser {
+    /// Starts in directory of `path`, or in working directory
+    pub fn new(mode: BrowserMode, path: Option<&Path>) -> Self {
+        let dir = (path.and_then(Path::parent))
+            .filter(|dir| dir.is_dir())
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let file_name = (path.and_then(Path::file_name))
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut browser = Self {
+            mode,
+            dir,
+            file_name,
+            entries: Vec::new(),
+            error: None,
+        };
+        browser.read_dir();
+        browser
+    }
+
+    pub fn mode(&self) -> BrowserMode {
+        self.mode
+    }
+
+    fn read_dir(&mut self) {
+        self.entries.clear();
+        self.error = None;
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let is_dir = path.is_dir();
+            let is_puzzle = path.extension().is_some_and(|ext| ext == PUZZLE_EXTENSION);
+            if !name.starts_with('.') && (is_dir || is_puzzle) {
+                self.entries.push((name, is_dir));
+            }
+        }
+        self.entries
+            .sort_by(|(name1, is_dir1), (name2, is_dir2)| (!is_dir1, name1).cmp(&(!is_dir2, name2)));
+    }
+
+    fn change_dir(&mut self, dir: PathBuf) {
+        self.dir = dir;
+        self.read_dir();
+    }
+
+    /// Saved files get puzzle extension if they have none
+    fn picked_path(&self) -> Option<PathBuf> {
+        let file_name = self.file_name.trim();
+        if file_name.is_empty() {
+            return None;
+        }
+        let mut path = self.dir.join(file_name);
+        if self.mode == BrowserMode::Save && path.extension().is_none() {
+            path.set_extension(PUZZLE_EXTENSION);
+        }
+        Some(path)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<BrowserResult> {
+        let title = match self.mode {
+            BrowserMode::Open => "Open puzzle",
+            BrowserMode::Save => "Save puzzle",
+        };
+        let mut result = None;
+        egui::Window::new(title)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Up").clicked()
+                        && let Some(parent) = self.dir.parent()
+                    {
+                        self.change_dir(parent.to_path_buf());
+                    }
+                    ui.label(self.dir.to_string_lossy());
+                });
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                let mut open_dir = None;
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (name, is_dir) in &self.entries {
+                        let text = if *is_dir { format!("{name}/") } else { name.clone() };
+                        let response = ui.selectable_label(!is_dir && *name == self.file_name, text);
+                        if *is_dir && response.clicked() {
+                            open_dir = Some(self.dir.join(name));
+                        } else if response.clicked() {
+                            self.file_name = name.clone();
+                        }
+                        if !is_dir && response.double_clicked() {
+                            result = Some(BrowserResult::Picked(self.dir.join(name)));
+                        }
+                    }
+                });
+                if let Some(dir) = open_dir {
+                    self.change_dir(dir);
+                }
+
+                ui.separator();
+                let response = ui.text_edit_singleline(&mut self.file_name);
+                let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    let picked_path = self.picked_path();
+                    let action = match self.mode {
+                        BrowserMode::Open => "Open",
+                        BrowserMode::Save => "Save",
+                    };
+                    let button = egui::Button::new(action);
+                    let clicked = ui.add_enabled(picked_path.is_some(), button).clicked();
+                    if let Some(path) = picked_path
+                        && (clicked || entered)
+                    {
+                        result = Some(BrowserResult::Picked(path));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        result = Some(BrowserResult::Cancelled);
+                    }
+                });
+            });
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_picked_path() {
+        let mut browser = FileBrowser::new(BrowserMode::Save, Some(Path::new("/tmp/old.ron")));
+        assert_eq!(Some(PathBuf::from("/tmp/old.ron")), browser.picked_path());
+        browser.file_name = "new".to_owned();
+        assert_eq!(Some(PathBuf::from("/tmp/new.ron")), browser.picked_path());
+        browser.file_name = " ".to_owned();
+        assert_eq!(None, browser.picked_path());
+
+        let mut browser = FileBrowser::new(BrowserMode::Open, Some(Path::new("/tmp/old.ron")));
+        browser.file_name = "new".to_owned();
+        assert_eq!(Some(PathBuf::from("/tmp/new")), browser.picked_path());
+    }
+}
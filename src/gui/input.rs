@@ -0,0 +1,308 @@
+use super::EguiDrawer;
+use crate::puzzle_logic::*;
+use eframe::egui::{self, Key, TouchPhase};
+
+const KEYBOARD_SPEED: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchEvent {
+    pub phase: TouchPhase,
+    pub pos: Dot,
+}
+
+/// Everything adapters need from egui for one frame, positions are in puzzle coords
+#[derive(Debug, Clone, Default)]
+pub struct InputFrame {
+    pub dt: f32,
+    pub pointer_pos: Option<Dot>,
+    pub primary_pressed: bool,
+    pub primary_down: bool,
+    pub secondary_pressed: bool,
+    /// Shift turns mouse into a pointer for picking a dot to retract to
+    pub shift_down: bool,
+    /// Ctrl, or Cmd on mac
+    pub command_down: bool,
+    /// Arrows and WASD, every axis is -1, 0 or 1
+    pub direction: Dot,
+    pub keys_pressed: Vec<Key>,
+    pub touches: Vec<TouchEvent>,
+}
+
+impl InputFrame {
+    pub fn read(ctx: &egui::Context, drawer: &EguiDrawer) -> Self {
+        ctx.input(|i| {
+            let axis = |positive: [Key; 2], negative: [Key; 2]| {
+                let is_down = |keys: [Key; 2]| keys.iter().any(|&key| i.key_down(key));
+                (is_down(positive) as i32 - is_down(negative) as i32) as f32
+            };
+            let direction = Dot::new(
+                axis([Key::ArrowRight, Key::D], [Key::ArrowLeft, Key::A]),
+                axis([Key::ArrowUp, Key::W], [Key::ArrowDown, Key::S]),
+            );
+            let keys_pressed = (i.events.iter())
+                .filter_map(|event| match event {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        repeat: false,
+                        ..
+                    } => Some(*key),
+                    _ => None,
+                })
+                .collect();
+            let touches: Vec<TouchEvent> = (i.events.iter())
+                .filter_map(|event| match event {
+                    egui::Event::Touch { phase, pos, .. } => Some(TouchEvent {
+                        phase: *phase,
+                        pos: drawer.get_dot(*pos),
+                    }),
+                    _ => None,
+                })
+                .collect();
+
+            // egui also emulates pointer from touches, they must not be handled twice
+            let is_touch = i.any_touches() || !touches.is_empty();
+            let pointer_pos = (i.pointer.hover_pos())
+                .filter(|_| !is_touch)
+                .map(|pos| drawer.get_dot(pos));
+            let primary_pressed = !is_touch && i.pointer.button_pressed(egui::PointerButton::Primary);
+            let secondary_pressed = !is_touch && i.pointer.button_pressed(egui::PointerButton::Secondary);
+
+            InputFrame {
+                dt: i.stable_dt,
+                pointer_pos,
+                primary_pressed,
+                primary_down: !is_touch && i.pointer.primary_down(),
+                secondary_pressed,
+                shift_down: i.modifiers.shift,
+                command_down: i.modifiers.command,
+                direction,
+                keys_pressed,
+                touches,
+            }
+        })
+    }
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+}
+
+/// Turns raw input of one device into intents for `PuzzleSolutionManager`
+pub trait InputAdapter {
+    fn intents(&mut self, frame: &InputFrame, puzzle: &Puzzle, is_solving: bool) -> Vec<InputIntent>;
+}
+
+/// How mouse moves the path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PointerMode {
+    /// Cursor is hidden and confined, path follows mouse deltas
+    #[default]
+    Grab,
+    /// Path follows visible cursor position, works in windowed and remote sessions
+    Absolute,
+}
+
+#[derive(Default)]
+pub struct MouseInput {
+    last_pos: Option<Dot>,
+    pub mode: PointerMode,
+}
+
+impl InputAdapter for MouseInput {
+    fn intents(&mut self, frame: &InputFrame, _puzzle: &Puzzle, is_solving: bool) -> Vec<InputIntent> {
+        let mut intents = Vec::new();
+        let is_picking = is_solving && frame.shift_down;
+        if let (Some(pos), Some(last_pos)) = (frame.pointer_pos, self.last_pos)
+            && !is_picking
+        {
+            intents.push(match self.mode {
+                PointerMode::Grab => InputIntent::MoveDelta(pos - last_pos),
+                PointerMode::Absolute => InputIntent::MoveTowards(pos),
+            });
+        }
+        self.last_pos = frame.pointer_pos;
+
+        if frame.primary_pressed
+            && let Some(pos) = frame.pointer_pos
+        {
+            intents.push(if is_picking {
+                InputIntent::RetractNear(pos)
+            } else if is_solving {
+                InputIntent::Submit
+            } else {
+                InputIntent::StartNear(pos)
+            });
+        }
+        if frame.secondary_pressed && is_solving {
+            intents.push(InputIntent::Abandon);
+        }
+        intents
+    }
+}
+
+#[derive(Default)]
+pub struct KeyboardInput {
+    selected_start: usize,
+}
+
+impl KeyboardInput {
+    pub fn selected_start(&self, puzzle: &Puzzle) -> Option<DotIndex> {
+        let start_dots = &puzzle.start_dots;
+        (!start_dots.is_empty()).then(|| start_dots[self.selected_start % start_dots.len()])
+    }
+}
+
+impl InputAdapter for KeyboardInput {
+    fn intents(&mut self, frame: &InputFrame, puzzle: &Puzzle, is_solving: bool) -> Vec<InputIntent> {
+        let mut intents = Vec::new();
+        if is_solving {
+            if frame.direction.length2() > 0.0 {
+                intents.push(InputIntent::MoveDirection(frame.direction, KEYBOARD_SPEED * frame.dt));
+            }
+            if frame.key_pressed(Key::Backspace) {
+                intents.push(InputIntent::Undo);
+            }
+            if frame.key_pressed(Key::Escape) {
+                intents.push(InputIntent::Abandon);
+            }
+            if frame.key_pressed(Key::Enter) {
+                intents.push(InputIntent::Submit);
+            }
+        } else {
+            if frame.key_pressed(Key::Tab) {
+                self.selected_start = self.selected_start.wrapping_add(1);
+            }
+            if frame.key_pressed(Key::Space)
+                && let Some(start_dot) = self.selected_start(puzzle)
+            {
+                intents.push(InputIntent::StartAt(start_dot));
+            }
+        }
+        intents
+    }
+}
+
+/// Path follows absolute finger position, lifting the finger submits
+#[derive(Default)]
+pub struct TouchInput {
+    is_touching: bool,
+}
+
+impl InputAdapter for TouchInput {
+    fn intents(&mut self, frame: &InputFrame, _puzzle: &Puzzle, is_solving: bool) -> Vec<InputIntent> {
+        let mut intents = Vec::new();
+        for touch in &frame.touches {
+            match touch.phase {
+                TouchPhase::Start => {
+                    if !is_solving {
+                        intents.push(InputIntent::StartNear(touch.pos));
+                    }
+                    self.is_touching = true;
+                }
+                TouchPhase::Move => {
+                    if self.is_touching {
+                        intents.push(InputIntent::MoveTowards(touch.pos));
+                    }
+                }
+                TouchPhase::End => {
+                    if self.is_touching {
+                        intents.push(InputIntent::Submit);
+                    }
+                    self.is_touching = false;
+                }
+                TouchPhase::Cancel => {
+                    intents.push(InputIntent::Abandon);
+                    self.is_touching = false;
+                }
+            }
+        }
+        intents
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mouse_input() {
+        let puzzle = Puzzle::default();
+        let mut mouse = MouseInput::default();
+        let mut frame = InputFrame {
+            pointer_pos: Some(Dot::new(0.1, 0.2)),
+            primary_pressed: true,
+            ..InputFrame::default()
+        };
+        let intents = mouse.intents(&frame, &puzzle, false);
+        assert_eq!(vec![InputIntent::StartNear(Dot::new(0.1, 0.2))], intents);
+
+        frame.pointer_pos = Some(Dot::new(0.3, 0.2));
+        let intents = mouse.intents(&frame, &puzzle, true);
+        assert_eq!(2, intents.len());
+        assert!(matches!(intents[0], InputIntent::MoveDelta(delta) if (delta.x - 0.2).abs() < 1e-6));
+        assert_eq!(InputIntent::Submit, intents[1]);
+
+        frame.pointer_pos = Some(Dot::new(0.5, 0.5));
+        frame.shift_down = true;
+        frame.secondary_pressed = true;
+        let intents = mouse.intents(&frame, &puzzle, true);
+        let expected = vec![InputIntent::RetractNear(Dot::new(0.5, 0.5)), InputIntent::Abandon];
+        assert_eq!(expected, intents);
+
+        mouse.mode = PointerMode::Absolute;
+        let frame = InputFrame {
+            pointer_pos: Some(Dot::new(0.4, 0.5)),
+            ..InputFrame::default()
+        };
+        let intents = mouse.intents(&frame, &puzzle, true);
+        assert_eq!(vec![InputIntent::MoveTowards(Dot::new(0.4, 0.5))], intents);
+    }
+
+    #[test]
+    fn test_keyboard_input() {
+        let mut puzzle = Puzzle::default();
+        puzzle.start_dots.push(DotIndex(3));
+        let mut keyboard = KeyboardInput::default();
+        let frame = InputFrame {
+            keys_pressed: vec![Key::Tab, Key::Space],
+            ..InputFrame::default()
+        };
+        let intents = keyboard.intents(&frame, &puzzle, false);
+        assert_eq!(vec![InputIntent::StartAt(DotIndex(3))], intents);
+
+        let frame = InputFrame {
+            dt: 0.5,
+            direction: Dot::new(1.0, 0.0),
+            keys_pressed: vec![Key::Enter],
+            ..InputFrame::default()
+        };
+        let intents = keyboard.intents(&frame, &puzzle, true);
+        let expected = vec![
+            InputIntent::MoveDirection(Dot::new(1.0, 0.0), KEYBOARD_SPEED * 0.5),
+            InputIntent::Submit,
+        ];
+        assert_eq!(expected, intents);
+    }
+
+    #[test]
+    fn test_touch_input() {
+        let puzzle = Puzzle::default();
+        let mut touch = TouchInput::default();
+        let touch_at = |phase: TouchPhase, x: f32, y: f32| TouchEvent { phase, pos: Dot::new(x, y) };
+        let frame = InputFrame {
+            touches: vec![touch_at(TouchPhase::Start, 0.2, 0.2), touch_at(TouchPhase::Move, 0.3, 0.2)],
+            ..InputFrame::default()
+        };
+        let expected = vec![
+            InputIntent::StartNear(Dot::new(0.2, 0.2)),
+            InputIntent::MoveTowards(Dot::new(0.3, 0.2)),
+        ];
+        assert_eq!(expected, touch.intents(&frame, &puzzle, false));
+
+        let frame = InputFrame {
+            touches: vec![touch_at(TouchPhase::End, 0.3, 0.2), touch_at(TouchPhase::Move, 0.4, 0.2)],
+            ..InputFrame::default()
+        };
+        assert_eq!(vec![InputIntent::Submit], touch.intents(&frame, &puzzle, true));
+    }
+}
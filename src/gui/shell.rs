@@ -0,0 +1,50 @@
+use super::{EditorApp, SolverApp};
+use crate::puzzle_logic::*;
+use eframe::egui;
+use std::path::PathBuf;
+
+const SWITCH_MODE_KEY: egui::Key = egui::Key::F2;
+
+/// Owns the edited puzzle, F2 switches between editing it and play-testing it
+pub struct AppShell {
+    editor: EditorApp,
+    /// Play-test of the puzzle as it was when switched, `None` while editing
+    solver: Option<SolverApp>,
+}
+
+impl eframe::App for AppShell {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let switch_pressed = ctx.input(|i| i.key_pressed(SWITCH_MODE_KEY));
+        // editor asks what to do with unsaved changes
+        let close_requested = ctx.input(|i| i.viewport().close_requested());
+        let close_to_editor = close_requested && self.solver.is_some() && self.editor.is_dirty();
+        if switch_pressed || close_to_editor {
+            match self.solver.take() {
+                Some(mut solver) => solver.release_cursor(ctx),
+                None => self.solver = Some(SolverApp::from_puzzle(self.editor.puzzle())),
+            }
+        }
+        match &mut self.solver {
+            Some(solver) => solver.update(ctx, frame),
+            None => self.editor.update(ctx, frame),
+        }
+    }
+}
+
+/// Which part of the shell is shown first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellMode {
+    Edit,
+    Play,
+}
+
+impl AppShell {
+    /// Play mode continues trace of the previous run
+    pub fn new(cc: &eframe::CreationContext<'_>, puzzle: Puzzle, path: Option<PathBuf>, mode: ShellMode) -> Self {
+        let solver = (mode == ShellMode::Play).then(|| SolverApp::new(cc, &puzzle));
+        Self {
+            editor: EditorApp::new(cc, puzzle, path),
+            solver,
+        }
+    }
+}
@@ -1,36 +1,83 @@
 use super::EguiDrawer;
+use super::input::{InputAdapter, InputFrame, KeyboardInput, MouseInput, PointerMode, TouchInput};
 use crate::puzzle_logic::*;
 use eframe::egui;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
 
-pub struct SolverApp<'a> {
-    puzzle: &'a Puzzle,
-    solution_manager: PuzzleSolutionManager<'a>,
+/// Unfinished trace is kept in this file of the config dir between runs
+const SOLVER_STATE_FILE: &str = "solver-state.ron";
+
+pub struct SolverApp {
+    solution_manager: PuzzleSolutionManager,
     drawer: EguiDrawer,
     is_grabbing_cursor: bool,
+
+    mouse: MouseInput,
+    keyboard: KeyboardInput,
+    touch: TouchInput,
+
+    hint: BackgroundHint,
+    show_hint: bool,
+
+    events: Receiver<SolutionEvent>,
+    status: Option<String>,
+
+    recording: Option<Recording>,
 }
 
-impl eframe::App for SolverApp<'_> {
+impl eframe::App for SolverApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.drawer.update(ctx);
-
-        if let Some(delta) = self.drawer.get_mouse_delta() {
-            self.solution_manager.update_mouse(Dot::new(delta.x, delta.y));
-        }
-        if self.drawer.clicked() {
-            if let Some(pos) = self.drawer.get_mouse_pos() {
-                let is_solving = self.solution_manager.click(Dot::new(pos.x, pos.y));
-                self.is_grabbing_cursor = is_solving;
-
-                let cursor_grab = if is_solving {
-                    egui::CursorGrab::Confined
-                } else {
-                    egui::CursorGrab::None
-                };
-                ctx.send_viewport_cmd(egui::ViewportCommand::CursorGrab(cursor_grab));
+        let input = InputFrame::read(ctx, &self.drawer);
+
+        let is_solving = self.solution_manager.is_solving();
+        let mouse_intents = self.mouse.intents(&input, self.solution_manager.puzzle(), is_solving);
+        let mouse_started = mouse_intents.iter().any(|x| matches!(x, InputIntent::StartNear(_)));
+        let intents: Vec<InputIntent> = (mouse_intents.into_iter())
+            .chain(self.keyboard.intents(&input, self.solution_manager.puzzle(), is_solving))
+            .chain(self.touch.intents(&input, self.solution_manager.puzzle(), is_solving))
+            .collect();
+        if let Some(recording) = &mut self.recording {
+            recording.push_frame(input.dt, &intents);
+        }
+        for intent in intents {
+            if self.solution_manager.apply(intent).is_err() {
+                self.solution_manager.abandon();
             }
         }
 
-        let cursor_icon = if self.is_grabbing_cursor {
+        let is_solving = self.solution_manager.is_solving();
+        if mouse_started && is_solving && self.mouse.mode == PointerMode::Grab {
+            self.set_cursor_grab(ctx, true);
+        } else if !is_solving && self.is_grabbing_cursor {
+            self.set_cursor_grab(ctx, false);
+        }
+
+        for event in self.events.try_iter() {
+            self.status = match event {
+                SolutionEvent::Started(_) | SolutionEvent::Abandoned => None,
+                SolutionEvent::Solved => Some("Correct solution!".to_owned()),
+                SolutionEvent::Failed(err) => Some(format!("Incorrect: {err:?}")),
+            };
+        }
+
+        if input.key_pressed(egui::Key::H) {
+            self.show_hint = !self.show_hint;
+        }
+        if input.key_pressed(egui::Key::M) {
+            self.toggle_pointer_mode(ctx);
+        }
+        if input.key_pressed(egui::Key::F5) {
+            self.toggle_recording();
+        }
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.save_state();
+        }
+
+        let is_picking = self.solution_manager.is_solving() && input.shift_down;
+        let cursor_icon = if self.is_grabbing_cursor && !is_picking {
             egui::CursorIcon::None
         } else {
             egui::CursorIcon::Default
@@ -38,27 +85,218 @@ impl eframe::App for SolverApp<'_> {
         ctx.set_cursor_icon(cursor_icon);
 
         egui::CentralPanel::default().show(ctx, |ui| {
+            self.drawer.update(ui.max_rect());
             ui.painter().rect_filled(
-                ctx.screen_rect(),
+                ui.max_rect(),
                 egui::CornerRadius::ZERO,
-                self.puzzle.background_color,
+                self.solution_manager.puzzle().background_color,
             );
-            self.drawer.draw_puzzle(ui, self.puzzle);
-            self.drawer.draw_path(ui, self.puzzle, &self.solution_manager);
-            // self.drawer.draw_debug_pane_connections(ui, self.puzzle, &self.solution_manager);
+            self.drawer.draw_puzzle(ui, self.solution_manager.puzzle());
+            if !self.solution_manager.is_solving() {
+                self.draw_selected_start(ui);
+            }
+            self.drawer.draw_path(ui, self.solution_manager.puzzle(), &self.solution_manager);
+            if self.show_hint {
+                self.hint.request(self.solution_manager.puzzle(), self.solution_manager.dot_path());
+                if let Some(hint) = self.hint.poll() {
+                    self.drawer.draw_hint(ui, self.solution_manager.puzzle(), &self.solution_manager, hint);
+                }
+            } else {
+                self.hint.stop();
+            }
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+            // self.drawer.draw_debug_pane_connections(ui, self.solution_manager.puzzle(), &self.solution_manager);
 
             ctx.request_repaint();
         });
     }
 }
 
-impl<'a> SolverApp<'a> {
-    pub fn new(_cc: &eframe::CreationContext<'_>, puzzle: &'a Puzzle) -> Self {
+impl SolverApp {
+    /// Continues unfinished trace of the previous run, if it was on the same puzzle
+    pub fn new(_cc: &eframe::CreationContext<'_>, puzzle: &Puzzle) -> Self {
+        let mut solver = Self::from_puzzle(puzzle);
+        if let Some(path) = solver_state_path()
+            && let Ok(text) = std::fs::read_to_string(path)
+            && let Ok(snapshot) = ron::from_str(&text)
+        {
+            // snapshot keeps puzzle fingerprint, so trace of other puzzle is rejected by restore
+            let _ = solver.solution_manager.restore(snapshot);
+        }
+        solver
+    }
+
+    /// Starts with empty trace
+    pub fn from_puzzle(puzzle: &Puzzle) -> Self {
+        let mut solution_manager = PuzzleSolutionManager::new(puzzle);
+        let events = solution_manager.subscribe();
         Self {
-            puzzle,
-            solution_manager: PuzzleSolutionManager::new(puzzle),
+            solution_manager,
             drawer: EguiDrawer::default(),
             is_grabbing_cursor: false,
+            mouse: MouseInput::default(),
+            keyboard: KeyboardInput::default(),
+            touch: TouchInput::default(),
+            hint: BackgroundHint::default(),
+            show_hint: false,
+            events,
+            status: None,
+            recording: None,
+        }
+    }
+
+    /// Gives cursor back, must be called before solver stops being updated
+    pub fn release_cursor(&mut self, ctx: &egui::Context) {
+        if self.is_grabbing_cursor {
+            self.set_cursor_grab(ctx, false);
+        }
+        ctx.set_cursor_icon(egui::CursorIcon::Default);
+    }
+
+    fn toggle_pointer_mode(&mut self, ctx: &egui::Context) {
+        self.mouse.mode = match self.mouse.mode {
+            PointerMode::Grab => PointerMode::Absolute,
+            PointerMode::Absolute => PointerMode::Grab,
+        };
+        if self.is_grabbing_cursor {
+            self.set_cursor_grab(ctx, false);
+        }
+        self.status = Some(format!("Pointer mode: {:?}", self.mouse.mode));
+    }
+
+    fn save_state(&self) {
+        let Some(path) = solver_state_path() else {
+            return;
+        };
+        if let Ok(text) = ron::to_string(&self.solution_manager.snapshot())
+            && let Some(dir) = path.parent()
+            && std::fs::create_dir_all(dir).is_ok()
+        {
+            let _ = std::fs::write(path, text);
+        }
+    }
+
+    /// Starts recording from clean state, or saves recording with its replay result
+    fn toggle_recording(&mut self) {
+        let Some(mut recording) = self.recording.take() else {
+            self.solution_manager.abandon();
+            self.recording = Some(Recording::new(self.solution_manager.puzzle()));
+            self.status = Some("Recording...".to_owned());
+            return;
+        };
+        let seconds = (std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH))
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let path = format!("recording-{seconds}.ron");
+        let saved = (recording.bless().map_err(|err| err.to_string()))
+            .and_then(|()| recording.to_ron().map_err(|err| err.to_string()))
+            .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+        self.status = Some(match saved {
+            Ok(()) => format!("Recording saved to {path}"),
+            Err(err) => format!("Can't save recording: {err}"),
+        });
+    }
+
+    fn set_cursor_grab(&mut self, ctx: &egui::Context, grab: bool) {
+        self.is_grabbing_cursor = grab;
+        let cursor_grab = if grab {
+            egui::CursorGrab::Confined
+        } else {
+            egui::CursorGrab::None
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::CursorGrab(cursor_grab));
+    }
+
+    fn draw_selected_start(&self, ui: &mut egui::Ui) {
+        let puzzle = self.solution_manager.puzzle();
+        if puzzle.start_dots.len() < 2 {
+            return;
+        }
+        let Some(start_dot) = self.keyboard.selected_start(puzzle) else {
+            return;
+        };
+        let dot = puzzle.dots[start_dot.0 as usize];
+        let width = self.drawer.get_line_width(puzzle) * 1.5;
+        let color = puzzle.solution_color.gamma_multiply(0.5);
+        self.drawer.draw_dot(ui, dot, width, color);
+    }
+}
+
+/// `$XDG_CONFIG_HOME/the_witness`, `%APPDATA%\the_witness` or `~/.config/the_witness`,
+/// `None` when none of them is set
+fn solver_state_path() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    let config_dir = (env_dir("XDG_CONFIG_HOME"))
+        .or_else(|| env_dir("APPDATA"))
+        .or_else(|| env_dir("HOME").map(|home| home.join(".config")))?;
+    Some(config_dir.join("the_witness").join(SOLVER_STATE_FILE))
+}
+
+/// Searches hint for the current path on background thread, search for an old path is cancelled
+struct BackgroundHint {
+    sender: Sender<(Vec<DotIndex>, Hint)>,
+    receiver: Receiver<(Vec<DotIndex>, Hint)>,
+    /// Path of the latest request
+    dot_path: Option<Vec<DotIndex>>,
+    cancel: Arc<AtomicBool>,
+    hint: Option<Hint>,
+}
+
+impl Default for BackgroundHint {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver,
+            dot_path: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            hint: None,
         }
     }
 }
+
+impl BackgroundHint {
+    /// Does nothing if hint for this path is already searched or found
+    fn request(&mut self, puzzle: &Puzzle, dot_path: &[DotIndex]) {
+        if self.dot_path.as_deref() == Some(dot_path) {
+            return;
+        }
+        self.stop();
+        self.dot_path = Some(dot_path.to_vec());
+        let puzzle = puzzle.clone();
+        let dot_path = dot_path.to_vec();
+        let cancel = self.cancel.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            if let Some(hint) = search_hint(&puzzle, &dot_path, &cancel) {
+                let _ = sender.send((dot_path, hint));
+            }
+        });
+    }
+    /// Hint for the path of the latest request, if it's ready
+    fn poll(&mut self) -> Option<Hint> {
+        for (dot_path, hint) in self.receiver.try_iter() {
+            if self.dot_path.as_ref() == Some(&dot_path) {
+                self.hint = Some(hint);
+            }
+        }
+        self.hint
+    }
+    fn stop(&mut self) {
+        if self.dot_path.is_none() {
+            return;
+        }
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.dot_path = None;
+        self.hint = None;
+    }
+}
+
+impl Drop for BackgroundHint {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
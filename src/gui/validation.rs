@@ -0,0 +1,139 @@
+use crate::puzzle_logic::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::{Duration, Instant};
+
+/// Solver stops after this many solutions, so big panels don't keep the thread busy
+pub const SOLUTION_LIMIT: usize = 1000;
+
+pub struct ValidationReport {
+    pub warnings: Vec<ValidationWarning>,
+    /// At most `SOLUTION_LIMIT`, empty if puzzle is broken
+    pub solutions: Vec<Solution>,
+    pub is_capped: bool,
+    pub elapsed: Duration,
+}
+
+impl ValidationReport {
+    /// `None` if `cancel` was set before solver finished
+    fn new(puzzle: &Puzzle, cancel: &AtomicBool) -> Option<Self> {
+        let warnings = validate_puzzle(puzzle);
+        let start = Instant::now();
+        let mut solutions = Vec::new();
+        if !warnings.iter().any(ValidationWarning::is_broken) {
+            solutions = PuzzleSolver::new(puzzle, Some(SOLUTION_LIMIT + 1))
+                .with_cancel(cancel)
+                .solve();
+        }
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        let elapsed = start.elapsed();
+        let is_capped = solutions.len() > SOLUTION_LIMIT;
+        solutions.truncate(SOLUTION_LIMIT);
+        Some(Self {
+            warnings,
+            solutions,
+            is_capped,
+            elapsed,
+        })
+    }
+}
+
+/// Validates puzzle on background thread, only report for the latest puzzle is kept
+/// and validation of older puzzles is cancelled
+pub struct BackgroundValidator {
+    sender: Sender<(u64, ValidationReport)>,
+    receiver: Receiver<(u64, ValidationReport)>,
+    generation: u64,
+    cancel: Arc<AtomicBool>,
+    report: Option<ValidationReport>,
+    is_running: bool,
+}
+
+impl Default for BackgroundValidator {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver,
+            generation: 0,
+            cancel: Arc::new(AtomicBool::new(false)),
+            report: None,
+            is_running: false,
+        }
+    }
+}
+
+impl BackgroundValidator {
+    /// Old report stays until the new one is ready
+    pub fn start(&mut self, puzzle: &Puzzle) {
+        self.generation += 1;
+        self.is_running = true;
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let generation = self.generation;
+        let puzzle = puzzle.clone();
+        let sender = self.sender.clone();
+        let cancel = self.cancel.clone();
+        std::thread::spawn(move || {
+            if let Some(report) = ValidationReport::new(&puzzle, &cancel) {
+                let _ = sender.send((generation, report));
+            }
+        });
+    }
+    /// Returns true if report for the latest puzzle has arrived
+    pub fn poll(&mut self) -> bool {
+        let mut is_updated = false;
+        for (generation, report) in self.receiver.try_iter() {
+            if generation == self.generation {
+                self.report = Some(report);
+                self.is_running = false;
+                is_updated = true;
+            }
+        }
+        is_updated
+    }
+    pub fn report(&self) -> Option<&ValidationReport> {
+        self.report.as_ref()
+    }
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+}
+
+impl Drop for BackgroundValidator {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_background_validator() {
+        let mut validator = BackgroundValidator::default();
+        let mut puzzle = Puzzle::default();
+        validator.start(&puzzle);
+        puzzle.start_dots.clear();
+        validator.start(&puzzle);
+        while !validator.poll() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        // report of the first puzzle is dropped
+        let report = validator.report().unwrap();
+        assert_eq!(vec![ValidationWarning::NoStart], report.warnings);
+        assert!(report.solutions.is_empty());
+        assert!(!validator.is_running());
+    }
+
+    #[test]
+    fn test_cancelled_report() {
+        let puzzle = Puzzle::default();
+        assert!(ValidationReport::new(&puzzle, &AtomicBool::new(false)).is_some());
+        assert!(ValidationReport::new(&puzzle, &AtomicBool::new(true)).is_none());
+    }
+}
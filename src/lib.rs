@@ -1,2 +1,3 @@
 pub mod gui;
 pub mod puzzle_logic;
+pub mod svg;
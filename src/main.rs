@@ -1,15 +1,44 @@
-use the_witness::gui::{EditorApp, SolverApp};
+mod cli;
+
+use cli::{CliError, Command};
+use std::process::ExitCode;
+use the_witness::gui::{AppShell, ShellMode};
 use the_witness::puzzle_logic::Puzzle;
 
-fn main() -> eframe::Result {
-    let mut puzzle = Puzzle::default();
+const NEW_PUZZLE_SIZE: usize = 5;
+
+fn run_gui(path: Option<std::path::PathBuf>, mode: ShellMode) -> Result<(), CliError> {
+    let puzzle = match &path {
+        // edited file may not exist yet
+        Some(path) if mode == ShellMode::Edit && !path.exists() => {
+            Puzzle::playable_grid(NEW_PUZZLE_SIZE, NEW_PUZZLE_SIZE)
+        }
+        Some(path) => cli::load_puzzle(path)?,
+        None => Puzzle::default(),
+    };
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "The Witness",
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(SolverApp::new(cc, &mut puzzle)))
+            Ok(Box::new(AppShell::new(cc, puzzle, path, mode)))
         }),
     )
+    .map_err(|err| CliError::Failed(err.to_string()))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = cli::parse_args(&args).and_then(|command| match command {
+        Command::Gui { path, mode } => run_gui(path, mode),
+        command => cli::run(command),
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
@@ -1,13 +1,45 @@
+mod deduction;
+mod difficulty;
+mod edge_analysis;
+mod edit_history;
+mod events;
+mod generator;
+mod grid;
+mod hint;
+mod input;
+mod minimizer;
 mod puzzle;
+mod recording;
 mod solution_checker;
 mod solution_manager;
+mod solver;
+mod style;
+mod topology;
+mod validation;
 
 pub use puzzle::{ComplexityColor, DotComplexity, LineComplexity, PaneComplexity};
 pub use puzzle::{Dot, DotIndex, LineIndex, PaneIndex, Puzzle};
-pub use solution_checker::{SolutionError, check_solution};
-pub use solution_manager::PuzzleSolutionManager;
+pub use deduction::{Deduction, DeductionFact, DeductionRule, DeductionStep, LineState, deduce};
+pub use difficulty::{DifficultyReport, estimate_difficulty};
+pub use edge_analysis::{EdgeClass, analyze_edges};
+pub use edit_history::{EditHistory, PuzzleEdit};
+pub use events::{EventSubscribers, SolutionEvent, SubmitOutcome};
+pub use generator::generate_puzzle;
+pub use grid::GridResize;
+pub use hint::{Hint, find_hint, search_hint};
+pub use input::InputIntent;
+pub use minimizer::{MinimizationReport, Symbol, minimize_puzzle, puzzle_symbols};
+pub use recording::{RecordedFrame, Recording, ReplayResult};
+pub use solution_checker::{SolutionError, check_path, check_solution, find_components, find_errors};
+pub use solution_manager::{ManagerError, ManagerSnapshot, PuzzleSolutionManager};
+pub use solver::{PuzzleSolver, Solution, SolverStats, find_solutions};
+pub use style::{PuzzleStyle, STYLE_PRESETS};
+pub use topology::TopologyError;
+pub use validation::{ValidationWarning, validate_puzzle};
 
 pub const LINE_BREAK_WIDTH: f32 = 0.4;
+/// Largest side of generated and edited grids, bigger ones are too slow to solve
+pub const MAX_GRID_SIZE: usize = 12;
 
 #[cfg(test)]
 mod test {
@@ -29,9 +61,28 @@ mod test {
             let delta = 0.02;
             let x: f32 = rng.random();
             let y: f32 = rng.random();
-            puzzle_manager.update_mouse(Dot::new(x, y).scale(delta));
+            puzzle_manager.update_mouse(Dot::new(x, y).scale(delta)).unwrap();
         }
         let correct_solution = check_solution(&puzzle_manager);
         assert!(correct_solution.is_ok());
     }
+
+    #[test]
+    fn move_with_steady_steps() {
+        let puzzle = Puzzle::default();
+        let mut puzzle_manager = PuzzleSolutionManager::new(&puzzle);
+        assert!(!puzzle_manager.start_at(DotIndex(1)));
+        assert!(puzzle_manager.start_at(puzzle.start_dots[0]));
+
+        // go right until next dot and then back
+        let step = Dot::new(0.01, 0.0);
+        for _ in 0..30 {
+            puzzle_manager.update_mouse(step).unwrap();
+        }
+        assert_eq!(2, puzzle_manager.dot_path().len());
+        for _ in 0..30 {
+            puzzle_manager.update_mouse(step.scale(-1.0)).unwrap();
+        }
+        assert_eq!(&vec![puzzle.start_dots[0]], puzzle_manager.dot_path());
+    }
 }
@@ -0,0 +1,443 @@
+use super::*;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineState {
+    Unknown,
+    Used,
+    Forbidden,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeductionRule {
+    LineBreak,
+    LineHexagon,
+    DotHexagon,
+    DifferentSquares,
+    DeadEnd,
+    PathCantBranch,
+    PathMustContinue,
+    HexagonDotPassage,
+    SingleStartOrEnd,
+    NoLoops,
+    PathComplete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeductionFact {
+    Line(LineIndex, LineState),
+    DotUsed(DotIndex),
+    Contradiction(DotIndex),
+    /// Line is required and forbidden by rules that don't involve a dot
+    LineContradiction(LineIndex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeductionStep {
+    pub rule: DeductionRule,
+    pub fact: DeductionFact,
+}
+
+pub struct Deduction {
+    pub steps: Vec<DeductionStep>,
+    pub line_states: HashMap<LineIndex, LineState>,
+    pub contradiction: bool,
+}
+
+impl Display for DeductionRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            DeductionRule::LineBreak => "line break removes edge",
+            DeductionRule::LineHexagon => "hexagon on an edge forces it and both endpoints",
+            DeductionRule::DotHexagon => "hexagon on a dot forces the dot",
+            DeductionRule::DifferentSquares => "different-colored squares in adjacent panes force the separating edge",
+            DeductionRule::DeadEnd => "dead-end dot cannot be entered",
+            DeductionRule::PathCantBranch => "dot with two used edges can't have more",
+            DeductionRule::PathMustContinue => "path entering a dot must leave it by the only free edge",
+            DeductionRule::HexagonDotPassage => "used dot with only two free edges uses both",
+            DeductionRule::SingleStartOrEnd => "start and end dots have exactly one edge",
+            DeductionRule::NoLoops => "edge closing a loop is forbidden",
+            DeductionRule::PathComplete => "path from start to end is complete, other edges are unused",
+        };
+        write!(f, "{text}")
+    }
+}
+impl Display for DeductionStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.fact {
+            DeductionFact::Line(line, LineState::Used) => write!(f, "{}: {line} is used", self.rule),
+            DeductionFact::Line(line, LineState::Forbidden) => write!(f, "{}: {line} is forbidden", self.rule),
+            DeductionFact::Line(line, LineState::Unknown) => write!(f, "{}: {line} is unknown", self.rule),
+            DeductionFact::DotUsed(dot) => write!(f, "{}: dot {dot} is used", self.rule),
+            DeductionFact::Contradiction(dot) => write!(f, "{}: contradiction at dot {dot}", self.rule),
+            DeductionFact::LineContradiction(line) => write!(f, "{}: contradiction at {line}", self.rule),
+        }
+    }
+}
+
+impl Deduction {
+    /// All lines are decided, so puzzle is solvable without guessing
+    pub fn is_solved(&self) -> bool {
+        !self.contradiction && self.line_states.values().all(|&state| state != LineState::Unknown)
+    }
+    pub fn used_lines(&self) -> Vec<LineIndex> {
+        let mut lines: Vec<_> = (self.line_states.iter())
+            .filter(|(_, state)| **state == LineState::Used)
+            .map(|(line, _)| *line)
+            .collect();
+        lines.sort();
+        lines
+    }
+}
+
+/// Logical solver, applies named rules until nothing new can be deduced
+struct Deducer<'a> {
+    puzzle: &'a Puzzle,
+    near_lines: Vec<Vec<usize>>,
+    states: Vec<LineState>,
+    used_dots: Vec<bool>,
+    steps: Vec<DeductionStep>,
+    contradiction: bool,
+}
+
+pub fn deduce(puzzle: &Puzzle) -> Deduction {
+    let mut deducer = Deducer::new(puzzle);
+    deducer.run();
+    Deduction {
+        line_states: puzzle.lines.iter().copied().zip(deducer.states).collect(),
+        steps: deducer.steps,
+        contradiction: deducer.contradiction,
+    }
+}
+
+impl<'a> Deducer<'a> {
+    fn new(puzzle: &'a Puzzle) -> Self {
+        let mut near_lines = vec![Vec::new(); puzzle.dots.len()];
+        for (i, line) in puzzle.lines.iter().enumerate() {
+            near_lines[line.0.0 as usize].push(i);
+            near_lines[line.1.0 as usize].push(i);
+        }
+        Self {
+            puzzle,
+            near_lines,
+            states: vec![LineState::Unknown; puzzle.lines.len()],
+            used_dots: vec![false; puzzle.dots.len()],
+            steps: Vec::new(),
+            contradiction: false,
+        }
+    }
+
+    fn run(&mut self) {
+        self.apply_symbols();
+        loop {
+            let steps_count = self.steps.len();
+            self.apply_dot_rules();
+            self.apply_no_loops();
+            self.apply_path_complete();
+            if self.contradiction || steps_count == self.steps.len() {
+                break;
+            }
+        }
+    }
+
+    fn set_line(&mut self, rule: DeductionRule, i: usize, state: LineState) {
+        self.update_line(rule, i, state, None);
+    }
+    /// Same as `set_line` for rules about one dot, contradiction is reported at that dot
+    fn set_dot_line(&mut self, rule: DeductionRule, dot: DotIndex, i: usize, state: LineState) {
+        self.update_line(rule, i, state, Some(dot));
+    }
+    fn update_line(&mut self, rule: DeductionRule, i: usize, state: LineState, dot: Option<DotIndex>) {
+        if self.contradiction || self.states[i] == state {
+            return;
+        }
+        let line = self.puzzle.lines[i];
+        if self.states[i] != LineState::Unknown {
+            self.contradiction = true;
+            self.steps.push(DeductionStep {
+                rule,
+                fact: match dot {
+                    Some(dot) => DeductionFact::Contradiction(dot),
+                    None => DeductionFact::LineContradiction(line),
+                },
+            });
+            return;
+        }
+        self.states[i] = state;
+        self.steps.push(DeductionStep {
+            rule,
+            fact: DeductionFact::Line(line, state),
+        });
+        if state == LineState::Used {
+            self.set_dot_used(rule, line.0);
+            self.set_dot_used(rule, line.1);
+        }
+    }
+    fn set_dot_used(&mut self, rule: DeductionRule, dot: DotIndex) {
+        if !self.used_dots[dot.0 as usize] {
+            self.used_dots[dot.0 as usize] = true;
+            self.steps.push(DeductionStep {
+                rule,
+                fact: DeductionFact::DotUsed(dot),
+            });
+        }
+    }
+    fn set_contradiction(&mut self, rule: DeductionRule, dot: DotIndex) {
+        if !self.contradiction {
+            self.contradiction = true;
+            self.steps.push(DeductionStep {
+                rule,
+                fact: DeductionFact::Contradiction(dot),
+            });
+        }
+    }
+    fn line_position(&self, line: LineIndex) -> Option<usize> {
+        self.puzzle.lines.iter().position(|&x| x == line)
+    }
+
+    fn apply_symbols(&mut self) {
+        let mut lines: Vec<_> = self.puzzle.line_complexity.iter().map(|(&k, &v)| (k, v)).collect();
+        lines.sort_by_key(|(line, _)| *line);
+        for (line, complexity) in lines {
+            let Some(i) = self.line_position(line) else { continue };
+            match complexity {
+                LineComplexity::LineBreak => self.set_line(DeductionRule::LineBreak, i, LineState::Forbidden),
+                LineComplexity::BlackHexagon => self.set_line(DeductionRule::LineHexagon, i, LineState::Used),
+            }
+        }
+
+        let mut dots: Vec<_> = self.puzzle.dot_complexity.keys().copied().collect();
+        dots.sort();
+        for dot in dots {
+            self.set_dot_used(DeductionRule::DotHexagon, dot);
+        }
+
+        for (pane, nears) in self.puzzle.pane_nears.iter().enumerate() {
+            let Some(PaneComplexity::Square(color)) = self.puzzle.pane_complexity.get(&PaneIndex(pane as u16)) else {
+                continue;
+            };
+            for (line, near_pane) in nears {
+                let Some(PaneComplexity::Square(near_color)) = self.puzzle.pane_complexity.get(near_pane) else {
+                    continue;
+                };
+                if color != near_color
+                    && let Some(i) = self.line_position(*line)
+                {
+                    self.set_line(DeductionRule::DifferentSquares, i, LineState::Used);
+                }
+            }
+        }
+    }
+
+    /// Start and end are known only when there is one of each
+    fn is_terminal(&self, dot: DotIndex) -> Option<bool> {
+        let is_start = self.puzzle.start_dots.contains(&dot);
+        let is_end = self.puzzle.end_dots.contains(&dot);
+        if !is_start && !is_end {
+            return Some(false);
+        }
+        let single = self.puzzle.start_dots.len() == 1 && self.puzzle.end_dots.len() == 1;
+        if single && is_start != is_end { Some(true) } else { None }
+    }
+
+    fn apply_dot_rules(&mut self) {
+        for dot_i in 0..self.puzzle.dots.len() {
+            let dot = DotIndex(dot_i as u16);
+            let Some(is_terminal) = self.is_terminal(dot) else {
+                continue;
+            };
+            let lines = self.near_lines[dot_i].clone();
+            let used = lines.iter().filter(|&&i| self.states[i] == LineState::Used).count();
+            let free: Vec<usize> = lines
+                .iter()
+                .copied()
+                .filter(|&i| self.states[i] == LineState::Unknown)
+                .collect();
+
+            if is_terminal {
+                let rule = DeductionRule::SingleStartOrEnd;
+                self.set_dot_used(rule, dot);
+                match (used, free.len()) {
+                    (0, 0) => self.set_contradiction(rule, dot),
+                    (0, 1) => self.set_dot_line(rule, dot, free[0], LineState::Used),
+                    (1, _) => free
+                        .iter()
+                        .for_each(|&i| self.set_dot_line(rule, dot, i, LineState::Forbidden)),
+                    (2.., _) => self.set_contradiction(rule, dot),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let is_used = self.used_dots[dot_i];
+            match (used, free.len()) {
+                (0, 0..=1) if is_used => self.set_contradiction(DeductionRule::DeadEnd, dot),
+                (0, 1) => self.set_dot_line(DeductionRule::DeadEnd, dot, free[0], LineState::Forbidden),
+                (0, 2) if is_used => free
+                    .iter()
+                    .for_each(|&i| self.set_dot_line(DeductionRule::HexagonDotPassage, dot, i, LineState::Used)),
+                (1, 0) => self.set_contradiction(DeductionRule::PathMustContinue, dot),
+                (1, 1) => self.set_dot_line(DeductionRule::PathMustContinue, dot, free[0], LineState::Used),
+                (2, _) => free
+                    .iter()
+                    .for_each(|&i| self.set_dot_line(DeductionRule::PathCantBranch, dot, i, LineState::Forbidden)),
+                (3.., _) => self.set_contradiction(DeductionRule::PathCantBranch, dot),
+                _ => {}
+            }
+        }
+    }
+
+    fn find_root(roots: &mut [usize], x: usize) -> usize {
+        let mut x = x;
+        while roots[x] != x {
+            roots[x] = roots[roots[x]];
+            x = roots[x];
+        }
+        x
+    }
+
+    fn apply_no_loops(&mut self) {
+        let mut roots: Vec<usize> = (0..self.puzzle.dots.len()).collect();
+        for (i, line) in self.puzzle.lines.iter().enumerate() {
+            if self.states[i] == LineState::Used {
+                let root0 = Self::find_root(&mut roots, line.0.0 as usize);
+                let root1 = Self::find_root(&mut roots, line.1.0 as usize);
+                roots[root0] = root1;
+            }
+        }
+        for (i, line) in self.puzzle.lines.iter().enumerate() {
+            if self.states[i] != LineState::Unknown {
+                continue;
+            }
+            let root0 = Self::find_root(&mut roots, line.0.0 as usize);
+            let root1 = Self::find_root(&mut roots, line.1.0 as usize);
+            if root0 == root1 {
+                self.set_line(DeductionRule::NoLoops, i, LineState::Forbidden);
+            }
+        }
+    }
+
+    fn apply_path_complete(&mut self) {
+        for start_i in 0..self.puzzle.start_dots.len() {
+            let start_dot = self.puzzle.start_dots[start_i];
+            let mut dot = start_dot;
+            let mut prev_line = None;
+            let mut length = 0;
+            loop {
+                let next_line = (self.near_lines[dot.0 as usize].iter())
+                    .find(|&&i| self.states[i] == LineState::Used && Some(i) != prev_line);
+                let Some(&next_line) = next_line else { break };
+                dot = self.puzzle.lines[next_line].other(dot);
+                prev_line = Some(next_line);
+                length += 1;
+                if dot == start_dot {
+                    break;
+                }
+            }
+
+            let used_count = self.states.iter().filter(|&&state| state == LineState::Used).count();
+            if length > 0 && length == used_count && self.puzzle.end_dots.contains(&dot) {
+                for i in 0..self.states.len() {
+                    if self.states[i] == LineState::Unknown {
+                        self.set_line(DeductionRule::PathComplete, i, LineState::Forbidden);
+                    }
+                }
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_against_solutions(puzzle: &Puzzle, deduction: &Deduction) {
+        for solution in find_solutions(puzzle, None) {
+            for (line, state) in &deduction.line_states {
+                match state {
+                    LineState::Used => assert!(solution.line_path.contains(line)),
+                    LineState::Forbidden => assert!(!solution.line_path.contains(line)),
+                    LineState::Unknown => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_deduce_default_puzzle() {
+        let puzzle = Puzzle::default();
+        let deduction = deduce(&puzzle);
+        assert!(!deduction.contradiction);
+
+        let line_break = puzzle.lines[8];
+        let hexagon_line = puzzle.lines[5];
+        assert_eq!(LineState::Forbidden, deduction.line_states[&line_break]);
+        assert_eq!(LineState::Used, deduction.line_states[&hexagon_line]);
+        check_against_solutions(&puzzle, &deduction);
+    }
+
+    #[test]
+    fn test_deduce_squares() {
+        let mut puzzle = Puzzle::default();
+        puzzle.pane_complexity.clear();
+        puzzle
+            .pane_complexity
+            .insert(PaneIndex(0), PaneComplexity::Square(ComplexityColor::Black));
+        puzzle
+            .pane_complexity
+            .insert(PaneIndex(1), PaneComplexity::Square(ComplexityColor::White));
+        let (separating_line, _) = puzzle.pane_nears[0]
+            .iter()
+            .find(|(_, pane)| *pane == PaneIndex(1))
+            .unwrap();
+
+        let deduction = deduce(&puzzle);
+        assert!(
+            deduction
+                .steps
+                .iter()
+                .any(|step| step.rule == DeductionRule::DifferentSquares)
+        );
+        assert_eq!(LineState::Used, deduction.line_states[separating_line]);
+        check_against_solutions(&puzzle, &deduction);
+    }
+
+    #[test]
+    fn test_line_contradiction() {
+        let mut puzzle = Puzzle::default();
+        puzzle.pane_complexity.clear();
+        puzzle.dot_complexity.clear();
+        puzzle.line_complexity.clear();
+        puzzle
+            .pane_complexity
+            .insert(PaneIndex(0), PaneComplexity::Square(ComplexityColor::Black));
+        puzzle
+            .pane_complexity
+            .insert(PaneIndex(1), PaneComplexity::Square(ComplexityColor::White));
+        let (separating_line, _) = puzzle.pane_nears[0]
+            .iter()
+            .find(|(_, pane)| *pane == PaneIndex(1))
+            .copied()
+            .unwrap();
+        puzzle
+            .line_complexity
+            .insert(separating_line, LineComplexity::LineBreak);
+
+        let deduction = deduce(&puzzle);
+        assert!(deduction.contradiction);
+        let step = (deduction.steps.iter())
+            .find(|step| step.rule == DeductionRule::DifferentSquares)
+            .unwrap();
+        assert_eq!(DeductionFact::LineContradiction(separating_line), step.fact);
+        let expected = format!("contradiction at {separating_line}");
+        assert!(step.to_string().ends_with(&expected));
+
+        let step = DeductionStep {
+            rule: DeductionRule::NoLoops,
+            fact: DeductionFact::Line(separating_line, LineState::Unknown),
+        };
+        assert!(step.to_string().ends_with("is unknown"));
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+use std::mem::{Discriminant, discriminant};
+
+#[derive(Debug, Clone)]
+pub struct DifficultyReport {
+    pub solutions: usize,
+    /// Size of search tree of the solver, which cuts dead ends and paths cut off from hexagons
+    pub search_nodes: usize,
+    /// Search states where the solver saw that no solution continues the path
+    pub pruned_nodes: usize,
+    /// Paths from start to end which the search reached, correct or not
+    pub complete_paths: usize,
+    /// Complete paths which break exactly one rule, they look like solutions for player
+    pub near_solutions: usize,
+    /// Average number of moves from dot during search
+    pub average_branching: f32,
+    /// Average number of moves from every dot, 0.0 if search never stood on it
+    pub dot_branching: Vec<f32>,
+    /// Number of different symbol kinds, squares of any color are one kind
+    pub rule_types: usize,
+    /// Combined estimate, bigger is harder, `None` if puzzle has no solution
+    pub score: Option<f32>,
+}
+
+#[derive(PartialEq, Eq)]
+enum RuleType {
+    Dot(Discriminant<DotComplexity>),
+    Line(Discriminant<LineComplexity>),
+    Pane(Discriminant<PaneComplexity>),
+}
+
+fn count_rule_types(puzzle: &Puzzle) -> usize {
+    let mut types = Vec::new();
+    let all_types = (puzzle.dot_complexity.values().map(|x| RuleType::Dot(discriminant(x))))
+        .chain(puzzle.line_complexity.values().map(|x| RuleType::Line(discriminant(x))))
+        .chain(puzzle.pane_complexity.values().map(|x| RuleType::Pane(discriminant(x))));
+    for rule_type in all_types {
+        if !types.contains(&rule_type) {
+            types.push(rule_type);
+        }
+    }
+    types.len()
+}
+
+/// Runs full search on puzzle, so it's slow on big panels
+pub fn estimate_difficulty(puzzle: &Puzzle) -> DifficultyReport {
+    let (solutions, stats) = PuzzleSolver::new(puzzle, None).solve_with_stats();
+
+    let ratio = |a: usize, b: usize| if b == 0 { 0.0 } else { a as f32 / b as f32 };
+    let dot_branching = (stats.dot_visits.iter().zip(&stats.dot_choices))
+        .map(|(&visits, &choices)| ratio(choices, visits))
+        .collect();
+    let average_branching = ratio(stats.dot_choices.iter().sum(), stats.dot_visits.iter().sum());
+    let rule_types = count_rule_types(puzzle);
+
+    let score = if solutions.is_empty() {
+        None
+    } else {
+        // how many wrong paths there are for every correct one
+        let rarity = ratio(stats.complete_paths, solutions.len()).log2();
+        // paths which are wrong only in one place are traps
+        let traps = ratio(stats.near_solutions, stats.complete_paths);
+        Some(rarity + traps * 4.0 + rule_types as f32 + average_branching)
+    };
+
+    DifficultyReport {
+        solutions: solutions.len(),
+        search_nodes: stats.nodes,
+        pruned_nodes: stats.pruned,
+        complete_paths: stats.complete_paths,
+        near_solutions: stats.near_solutions,
+        average_branching,
+        dot_branching,
+        rule_types,
+        score,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_symbols_make_puzzle_harder() {
+        let puzzle = Puzzle::default();
+        let mut empty_puzzle = puzzle.clone();
+        empty_puzzle.dot_complexity.clear();
+        empty_puzzle.line_complexity.clear();
+        empty_puzzle.pane_complexity.clear();
+
+        let report = estimate_difficulty(&puzzle);
+        let empty_report = estimate_difficulty(&empty_puzzle);
+        assert_eq!(4, report.rule_types);
+        assert_eq!(0, empty_report.rule_types);
+        assert_eq!(empty_report.solutions, empty_report.complete_paths);
+        assert!(report.score.unwrap() > empty_report.score.unwrap());
+    }
+}
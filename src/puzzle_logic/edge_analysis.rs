@@ -0,0 +1,55 @@
+use super::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeClass {
+    /// Used by every solution
+    Forced,
+    /// Used by no solution
+    Unused,
+    /// Used by some solutions
+    Varies,
+}
+
+/// Classifies every line of puzzle, `solutions` must be all solutions of puzzle
+pub fn analyze_edges(puzzle: &Puzzle, solutions: &[Solution]) -> HashMap<LineIndex, EdgeClass> {
+    let mut counts: HashMap<LineIndex, usize> = puzzle.lines.iter().map(|&line| (line, 0)).collect();
+    for solution in solutions {
+        for line in &solution.line_path {
+            if let Some(count) = counts.get_mut(line) {
+                *count += 1;
+            }
+        }
+    }
+
+    (counts.into_iter())
+        .map(|(line, count)| {
+            let class = match count {
+                0 => EdgeClass::Unused,
+                _ if count == solutions.len() => EdgeClass::Forced,
+                _ => EdgeClass::Varies,
+            };
+            (line, class)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_analyze_edges() {
+        let puzzle = Puzzle::default();
+        let solutions = find_solutions(&puzzle, None);
+        let classes = analyze_edges(&puzzle, &solutions);
+        assert_eq!(puzzle.lines.len(), classes.len());
+
+        let line_break = puzzle.lines[8];
+        let hexagon_line = puzzle.lines[5];
+        let end_line = *puzzle.lines.last().unwrap();
+        assert_eq!(EdgeClass::Unused, classes[&line_break]);
+        assert_eq!(EdgeClass::Forced, classes[&hexagon_line]);
+        assert_eq!(EdgeClass::Forced, classes[&end_line]);
+    }
+}
@@ -0,0 +1,226 @@
+use super::*;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// One reversible change of puzzle, keeps both old and new value
+#[derive(Clone)]
+pub enum PuzzleEdit {
+    DotComplexity {
+        dot: DotIndex,
+        old: Option<DotComplexity>,
+        new: Option<DotComplexity>,
+    },
+    LineComplexity {
+        line: LineIndex,
+        old: Option<LineComplexity>,
+        new: Option<LineComplexity>,
+    },
+    PaneComplexity {
+        pane: PaneIndex,
+        old: Option<PaneComplexity>,
+        new: Option<PaneComplexity>,
+    },
+    /// Topology and property changes, which touch many fields at once
+    Replace { old: Box<Puzzle>, new: Box<Puzzle> },
+    /// Several edits undone as one
+    Group(Vec<PuzzleEdit>),
+}
+
+fn set_value<K: Eq + Hash, V>(map: &mut HashMap<K, V>, key: K, value: Option<V>) {
+    match value {
+        Some(value) => map.insert(key, value),
+        None => map.remove(&key),
+    };
+}
+
+impl PuzzleEdit {
+    pub fn set_dot_complexity(puzzle: &Puzzle, dot: DotIndex, new: Option<DotComplexity>) -> Self {
+        let old = puzzle.dot_complexity.get(&dot).copied();
+        PuzzleEdit::DotComplexity { dot, old, new }
+    }
+    pub fn set_line_complexity(puzzle: &Puzzle, line: LineIndex, new: Option<LineComplexity>) -> Self {
+        let old = puzzle.line_complexity.get(&line).copied();
+        PuzzleEdit::LineComplexity { line, old, new }
+    }
+    pub fn set_pane_complexity(puzzle: &Puzzle, pane: PaneIndex, new: Option<PaneComplexity>) -> Self {
+        let old = puzzle.pane_complexity.get(&pane).copied();
+        PuzzleEdit::PaneComplexity { pane, old, new }
+    }
+    /// Replaces whole puzzle with edited copy
+    pub fn replace(puzzle: &Puzzle, new: Puzzle) -> Self {
+        PuzzleEdit::Replace {
+            old: Box::new(puzzle.clone()),
+            new: Box::new(new),
+        }
+    }
+
+    pub fn apply(&self, puzzle: &mut Puzzle) {
+        match self {
+            PuzzleEdit::DotComplexity { dot, new, .. } => set_value(&mut puzzle.dot_complexity, *dot, *new),
+            PuzzleEdit::LineComplexity { line, new, .. } => set_value(&mut puzzle.line_complexity, *line, *new),
+            PuzzleEdit::PaneComplexity { pane, new, .. } => set_value(&mut puzzle.pane_complexity, *pane, *new),
+            PuzzleEdit::Replace { new, .. } => *puzzle = (**new).clone(),
+            PuzzleEdit::Group(edits) => edits.iter().for_each(|edit| edit.apply(puzzle)),
+        }
+    }
+    pub fn revert(&self, puzzle: &mut Puzzle) {
+        match self {
+            PuzzleEdit::DotComplexity { dot, old, .. } => set_value(&mut puzzle.dot_complexity, *dot, *old),
+            PuzzleEdit::LineComplexity { line, old, .. } => set_value(&mut puzzle.line_complexity, *line, *old),
+            PuzzleEdit::PaneComplexity { pane, old, .. } => set_value(&mut puzzle.pane_complexity, *pane, *old),
+            PuzzleEdit::Replace { old, .. } => *puzzle = (**old).clone(),
+            PuzzleEdit::Group(edits) => edits.iter().rev().for_each(|edit| edit.revert(puzzle)),
+        }
+    }
+}
+
+/// Undo and redo stacks of puzzle edits, the oldest edits are forgotten after `limit`
+pub struct EditHistory {
+    undo_stack: VecDeque<PuzzleEdit>,
+    redo_stack: Vec<PuzzleEdit>,
+    limit: usize,
+    /// Length of undo stack when puzzle was saved, `None` if saved state can't be reached
+    saved_at: Option<usize>,
+}
+
+impl EditHistory {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            limit,
+            saved_at: Some(0),
+        }
+    }
+
+    /// Applies edit to puzzle and remembers it, redo stack is cleared
+    pub fn apply(&mut self, puzzle: &mut Puzzle, edit: PuzzleEdit) {
+        edit.apply(puzzle);
+        self.record(edit);
+    }
+
+    /// Remembers edit which is already applied, like dot drag finished by mouse release
+    pub fn record(&mut self, edit: PuzzleEdit) {
+        if self.saved_at.is_some_and(|saved_at| saved_at > self.undo_stack.len()) {
+            // saved state was in redo stack
+            self.saved_at = None;
+        }
+        self.undo_stack.push_back(edit);
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.pop_front();
+            self.saved_at = self.saved_at.and_then(|saved_at| saved_at.checked_sub(1));
+        }
+        self.redo_stack.clear();
+    }
+
+    /// returns false if there is nothing to undo
+    pub fn undo(&mut self, puzzle: &mut Puzzle) -> bool {
+        let Some(edit) = self.undo_stack.pop_back() else {
+            return false;
+        };
+        edit.revert(puzzle);
+        self.redo_stack.push(edit);
+        true
+    }
+    /// returns false if there is nothing to redo
+    pub fn redo(&mut self, puzzle: &mut Puzzle) -> bool {
+        let Some(edit) = self.redo_stack.pop() else {
+            return false;
+        };
+        edit.apply(puzzle);
+        self.undo_stack.push_back(edit);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+    /// Forgets all edits, current puzzle counts as saved
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.saved_at = Some(0);
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_at = Some(self.undo_stack.len());
+    }
+    /// Puzzle differs from the saved one, undoing back to saved state makes it clean again
+    pub fn is_dirty(&self) -> bool {
+        self.saved_at != Some(self.undo_stack.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut puzzle = Puzzle::default();
+        let mut history = EditHistory::new(2);
+        let pane = PaneIndex(0);
+        let square = Some(PaneComplexity::Square(ComplexityColor::White));
+
+        let edit = PuzzleEdit::set_pane_complexity(&puzzle, pane, square);
+        history.apply(&mut puzzle, edit);
+        let edit = PuzzleEdit::set_dot_complexity(&puzzle, DotIndex(2), None);
+        history.apply(&mut puzzle, edit);
+        let mut resized = puzzle.clone();
+        resized.line_width *= 2.0;
+        let edit = PuzzleEdit::replace(&puzzle, resized);
+        history.apply(&mut puzzle, edit);
+
+        assert!(history.undo(&mut puzzle));
+        assert_eq!(Puzzle::default().line_width, puzzle.line_width);
+        assert!(history.undo(&mut puzzle));
+        assert!(puzzle.dot_complexity.contains_key(&DotIndex(2)));
+        // first edit is forgotten because of the limit
+        assert!(!history.undo(&mut puzzle));
+        assert_eq!(square, puzzle.pane_complexity.get(&pane).copied());
+
+        assert!(history.redo(&mut puzzle));
+        assert!(puzzle.dot_complexity.is_empty());
+        let edit = PuzzleEdit::set_pane_complexity(&puzzle, pane, None);
+        history.apply(&mut puzzle, edit);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_dirty() {
+        let mut puzzle = Puzzle::default();
+        let mut history = EditHistory::new(2);
+        let edit = PuzzleEdit::set_dot_complexity(&puzzle, DotIndex(0), None);
+        assert!(!history.is_dirty());
+        history.apply(&mut puzzle, edit.clone());
+        assert!(history.is_dirty());
+        history.mark_saved();
+        assert!(!history.is_dirty());
+
+        history.undo(&mut puzzle);
+        assert!(history.is_dirty());
+        history.redo(&mut puzzle);
+        assert!(!history.is_dirty());
+
+        // saved state is dropped from redo stack
+        history.undo(&mut puzzle);
+        history.apply(&mut puzzle, edit.clone());
+        history.undo(&mut puzzle);
+        assert!(history.is_dirty());
+
+        // saved state is dropped because of the limit
+        history.mark_saved();
+        history.apply(&mut puzzle, edit.clone());
+        history.apply(&mut puzzle, edit.clone());
+        history.apply(&mut puzzle, edit.clone());
+        history.undo(&mut puzzle);
+        history.undo(&mut puzzle);
+        assert!(history.is_dirty());
+
+        history.clear();
+        assert!(!history.is_dirty());
+    }
+}
@@ -0,0 +1,67 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    /// There was no path to check
+    NotSolving,
+    Solved,
+    Failed(SolutionError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SolutionEvent {
+    Started(DotIndex),
+    Abandoned,
+    Solved,
+    Failed(SolutionError),
+}
+
+/// Sends events to every subscriber, forgets subscribers which dropped their receiver
+#[derive(Default)]
+pub struct EventSubscribers {
+    senders: Vec<Sender<SolutionEvent>>,
+}
+
+impl EventSubscribers {
+    pub fn subscribe(&mut self) -> Receiver<SolutionEvent> {
+        let (sender, receiver) = channel();
+        self.senders.push(sender);
+        receiver
+    }
+    pub fn send(&mut self, event: SolutionEvent) {
+        self.senders.retain(|sender| sender.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let puzzle = Puzzle::default();
+        let start_dot = puzzle.start_dots[0];
+        let mut manager = PuzzleSolutionManager::new(&puzzle);
+        let receiver = manager.subscribe();
+        {
+            let _dropped_receiver = manager.subscribe();
+        }
+
+        assert_eq!(SubmitOutcome::NotSolving, manager.submit());
+        manager.start_at(start_dot);
+        manager.abandon();
+        manager.start_at(start_dot);
+        assert_eq!(SubmitOutcome::Failed(SolutionError::Incomplete), manager.submit());
+
+        let events: Vec<_> = receiver.try_iter().collect();
+        let expected = vec![
+            SolutionEvent::Started(start_dot),
+            SolutionEvent::Abandoned,
+            SolutionEvent::Started(start_dot),
+            SolutionEvent::Failed(SolutionError::Incomplete),
+        ];
+        assert_eq!(expected, events);
+    }
+}
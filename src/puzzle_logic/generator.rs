@@ -0,0 +1,114 @@
+use super::*;
+use rand::prelude::*;
+
+const DOT_HEXAGON_CHANCE: f64 = 0.1;
+const LINE_HEXAGON_CHANCE: f64 = 0.05;
+const LINE_BREAK_CHANCE: f64 = 0.1;
+const SQUARE_CHANCE: f64 = 0.35;
+
+/// Random self-avoiding path from the first start dot to any end dot
+///
+/// Walk only steps to dots from which an end is still reachable through free dots,
+/// so it never gets stuck and never backtracks
+fn random_path(rng: &mut impl Rng, puzzle: &Puzzle) -> Option<Solution> {
+    let mut near_lines = vec![Vec::new(); puzzle.dots.len()];
+    for &line in &puzzle.lines {
+        near_lines[line.0.0 as usize].push(line);
+        near_lines[line.1.0 as usize].push(line);
+    }
+
+    let start_dot = *puzzle.start_dots.first()?;
+    let mut visited = vec![false; puzzle.dots.len()];
+    visited[start_dot.0 as usize] = true;
+    let mut dot_path = vec![start_dot];
+    let mut line_path = Vec::new();
+    let mut dot = start_dot;
+    while !puzzle.end_dots.contains(&dot) {
+        let mut lines = near_lines[dot.0 as usize].clone();
+        lines.shuffle(rng);
+        let line = *(lines.iter()).find(|line| {
+            let next_dot = line.other(dot);
+            !visited[next_dot.0 as usize] && reaches_end(puzzle, &near_lines, &visited, next_dot)
+        })?;
+        dot = line.other(dot);
+        visited[dot.0 as usize] = true;
+        dot_path.push(dot);
+        line_path.push(line);
+    }
+    Some(Solution { dot_path, line_path })
+}
+
+/// Is some end dot reachable from `from` without going through visited dots
+fn reaches_end(puzzle: &Puzzle, near_lines: &[Vec<LineIndex>], visited: &[bool], from: DotIndex) -> bool {
+    let mut reached = visited.to_vec();
+    reached[from.0 as usize] = true;
+    let mut stack = vec![from];
+    while let Some(dot) = stack.pop() {
+        if puzzle.end_dots.contains(&dot) {
+            return true;
+        }
+        for line in &near_lines[dot.0 as usize] {
+            let next_dot = line.other(dot);
+            if !reached[next_dot.0 as usize] {
+                reached[next_dot.0 as usize] = true;
+                stack.push(next_dot);
+            }
+        }
+    }
+    false
+}
+
+/// Random puzzle on grid, symbols are placed around random path, so it always has a solution
+pub fn generate_puzzle(rng: &mut impl Rng, width: usize, height: usize) -> Puzzle {
+    let mut puzzle = Puzzle::playable_grid(width, height);
+    let path = random_path(rng, &puzzle).expect("grid has path from start to end");
+
+    for &dot in &path.dot_path {
+        if rng.random_bool(DOT_HEXAGON_CHANCE) {
+            puzzle.dot_complexity.insert(dot, DotComplexity::BlackHexagon);
+        }
+    }
+    for &line in &puzzle.lines {
+        let on_path = path.line_path.contains(&line);
+        if on_path && rng.random_bool(LINE_HEXAGON_CHANCE) {
+            puzzle.line_complexity.insert(line, LineComplexity::BlackHexagon);
+        } else if !on_path && rng.random_bool(LINE_BREAK_CHANCE) {
+            puzzle.line_complexity.insert(line, LineComplexity::LineBreak);
+        }
+    }
+    // squares of one region share color
+    for component in find_components(&puzzle, &path.line_path) {
+        let color = if rng.random_bool(0.5) {
+            ComplexityColor::Black
+        } else {
+            ComplexityColor::White
+        };
+        for pane in component {
+            if rng.random_bool(SQUARE_CHANCE) {
+                puzzle.pane_complexity.insert(pane, PaneComplexity::Square(color));
+            }
+        }
+    }
+    puzzle
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_puzzle() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for (width, height) in [(2, 2), (4, 4), (6, 3)] {
+            let puzzle = generate_puzzle(&mut rng, width, height);
+            assert_eq!(Some((width, height)), puzzle.grid_size());
+            assert_eq!(1, find_solutions(&puzzle, Some(1)).len());
+        }
+        let puzzle = generate_puzzle(&mut rng, MAX_GRID_SIZE, MAX_GRID_SIZE);
+        assert_eq!(Some((MAX_GRID_SIZE, MAX_GRID_SIZE)), puzzle.grid_size());
+
+        let symbols1 = puzzle_symbols(&generate_puzzle(&mut StdRng::seed_from_u64(1), 5, 5));
+        let symbols2 = puzzle_symbols(&generate_puzzle(&mut StdRng::seed_from_u64(1), 5, 5));
+        assert_eq!(symbols1, symbols2);
+    }
+}
@@ -0,0 +1,177 @@
+use super::*;
+use std::collections::HashMap;
+
+/// Resized puzzle together with everything which didn't fit in it
+pub struct GridResize {
+    pub puzzle: Puzzle,
+    /// Symbols of old puzzle
+    pub dropped_symbols: Vec<Symbol>,
+    pub dropped_starts: Vec<DotIndex>,
+    pub dropped_ends: Vec<DotIndex>,
+}
+
+/// Column and row of every dot which isn't an end
+struct GridCoords {
+    width: usize,
+    height: usize,
+    coords: HashMap<DotIndex, (usize, usize)>,
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+}
+
+fn quantize(value: f32) -> i64 {
+    (value * 1e4).round() as i64
+}
+
+impl Puzzle {
+    fn grid_coords(&self) -> Option<GridCoords> {
+        let grid_dots: Vec<DotIndex> = (0..self.dots.len() as u16)
+            .map(DotIndex)
+            .filter(|dot| !self.end_dots.contains(dot))
+            .collect();
+        let unique = |get: fn(&Dot) -> f32| {
+            let mut values: Vec<f32> = grid_dots.iter().map(|dot| get(&self.dots[dot.0 as usize])).collect();
+            values.sort_by(f32::total_cmp);
+            values.dedup_by_key(|x| quantize(*x));
+            values
+        };
+        let (xs, ys) = (unique(|dot| dot.x), unique(|dot| dot.y));
+        let (width, height) = (xs.len(), ys.len());
+        if width < 2 || height < 2 || width * height != grid_dots.len() {
+            return None;
+        }
+
+        let position = |values: &[f32], value: f32| values.iter().position(|x| quantize(*x) == quantize(value));
+        let coords: HashMap<DotIndex, (usize, usize)> = (grid_dots.iter())
+            .map(|&dot| {
+                let Dot { x, y } = self.dots[dot.0 as usize];
+                (dot, (position(&xs, x).unwrap_or_default(), position(&ys, y).unwrap_or_default()))
+            })
+            .collect();
+
+        // every line is a lattice step or an end stub, and every lattice step is a line
+        let is_step = |(x1, y1): (usize, usize), (x2, y2): (usize, usize)| x1.abs_diff(x2) + y1.abs_diff(y2) == 1;
+        let lines_are_steps = self.lines.iter().all(|line| match (coords.get(&line.0), coords.get(&line.1)) {
+            (Some(&coords1), Some(&coords2)) => is_step(coords1, coords2),
+            _ => true,
+        });
+        let steps_count = (width - 1) * height + width * (height - 1);
+        let stubs_count = self.end_dots.len();
+        if !lines_are_steps || self.lines.len() != steps_count + stubs_count {
+            return None;
+        }
+        Some(GridCoords {
+            width,
+            height,
+            coords,
+            xs,
+            ys,
+        })
+    }
+
+    /// Columns and rows of dots, `None` if puzzle isn't a full grid
+    pub fn grid_size(&self) -> Option<(usize, usize)> {
+        self.grid_coords().map(|grid| (grid.width, grid.height))
+    }
+
+    /// New grid of other size, symbols, starts and ends keep their column and row if it still exists
+    pub fn resize_grid(&self, width: usize, height: usize) -> Option<GridResize> {
+        if width < 2 || height < 2 {
+            return None;
+        }
+        let grid = self.grid_coords()?;
+        let mut puzzle = Puzzle::grid(width, height);
+        puzzle.line_width = self.line_width;
+        puzzle.background_color = self.background_color;
+        puzzle.puzzle_color = self.puzzle_color;
+        puzzle.solution_color = self.solution_color;
+
+        let new_dot = |dot: DotIndex| {
+            let &(x, y) = grid.coords.get(&dot)?;
+            (x < width && y < height).then(|| DotIndex((x * height + y) as u16))
+        };
+        let new_line = |puzzle: &Puzzle, line: LineIndex| puzzle.find_line(new_dot(line.0)?, new_dot(line.1)?);
+        let new_pane = |pane: PaneIndex| {
+            let center = self.panes[pane.0 as usize];
+            let x = grid.xs.iter().filter(|x| **x < center.x).count().checked_sub(1)?;
+            let y = grid.ys.iter().filter(|y| **y < center.y).count().checked_sub(1)?;
+            (x + 1 < width && y + 1 < height).then(|| PaneIndex((y * (width - 1) + x) as u16))
+        };
+
+        let mut dropped_symbols = Vec::new();
+        for symbol in puzzle_symbols(self) {
+            let kept = match symbol {
+                Symbol::Dot(dot, complexity) => new_dot(dot).map(|dot| {
+                    puzzle.dot_complexity.insert(dot, complexity);
+                }),
+                Symbol::Line(line, complexity) => new_line(&puzzle, line).map(|line| {
+                    puzzle.line_complexity.insert(line, complexity);
+                }),
+                Symbol::Pane(pane, complexity) => new_pane(pane).map(|pane| {
+                    puzzle.pane_complexity.insert(pane, complexity);
+                }),
+            };
+            if kept.is_none() {
+                dropped_symbols.push(symbol);
+            }
+        }
+
+        let mut dropped_starts = Vec::new();
+        for &dot in &self.start_dots {
+            match new_dot(dot) {
+                Some(dot) => puzzle.start_dots.push(dot),
+                None => dropped_starts.push(dot),
+            }
+        }
+        let mut dropped_ends = Vec::new();
+        for &end_dot in &self.end_dots {
+            let base = (self.lines.iter()).find(|line| line.contains(end_dot)).map(|line| line.other(end_dot));
+            let added = base.and_then(|base| {
+                let direction = self.dots[end_dot.0 as usize] - self.dots[base.0 as usize];
+                puzzle.add_end_stub(new_dot(base)?, direction).ok()
+            });
+            if added.is_none() {
+                dropped_ends.push(end_dot);
+            }
+        }
+
+        Some(GridResize {
+            puzzle,
+            dropped_symbols,
+            dropped_starts,
+            dropped_ends,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resize_grid() {
+        let puzzle = Puzzle::default();
+        assert_eq!(Some((5, 5)), puzzle.grid_size());
+        let same = puzzle.resize_grid(5, 5).unwrap();
+        assert_eq!(puzzle_symbols(&puzzle), puzzle_symbols(&same.puzzle));
+        assert_eq!(puzzle.end_dots, same.puzzle.end_dots);
+
+        let bigger = puzzle.resize_grid(7, 6).unwrap();
+        assert_eq!(Some((7, 6)), bigger.puzzle.grid_size());
+        assert!(bigger.dropped_symbols.is_empty());
+        // end on the right side of old corner is inside now
+        assert_eq!(vec![DotIndex(25)], bigger.dropped_ends);
+        assert_eq!(5, puzzle_symbols(&bigger.puzzle).len());
+        assert_eq!(Some(&DotComplexity::BlackHexagon), bigger.puzzle.dot_complexity.get(&DotIndex(2)));
+        assert!(bigger.puzzle.pane_complexity.contains_key(&PaneIndex(6)));
+
+        let smaller = puzzle.resize_grid(3, 4).unwrap();
+        assert_eq!(vec![DotIndex(25)], smaller.dropped_ends);
+        let dropped_pane = Symbol::Pane(PaneIndex(3), PaneComplexity::Square(ComplexityColor::Black));
+        assert_eq!(vec![dropped_pane], smaller.dropped_symbols);
+
+        let mut not_grid = puzzle.clone();
+        not_grid.add_line(DotIndex(0), DotIndex(6)).unwrap();
+        assert!(not_grid.resize_grid(6, 6).is_none());
+    }
+}
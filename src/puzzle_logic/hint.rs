@@ -0,0 +1,131 @@
+use super::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    /// Next segment of some solution which continues current path
+    NextLine(LineIndex),
+    /// Current path is already a solution
+    Complete,
+    /// No solution continues current path, only first `correct_dots` dots of it are right
+    WrongTurn {
+        correct_dots: usize,
+    },
+    NoSolution,
+}
+
+/// Finds hint for path, `solutions` must be all solutions of puzzle
+pub fn find_hint(solutions: &[Solution], dot_path: &[DotIndex]) -> Hint {
+    if solutions.is_empty() {
+        return Hint::NoSolution;
+    }
+    if dot_path.is_empty() {
+        return Hint::NextLine(solutions[0].line_path[0]);
+    }
+
+    let mut correct_dots = 0;
+    for solution in solutions {
+        let common = (solution.dot_path.iter().zip(dot_path))
+            .take_while(|(dot1, dot2)| dot1 == dot2)
+            .count();
+        if common == dot_path.len() {
+            return match solution.line_path.get(dot_path.len() - 1) {
+                Some(&line) => Hint::NextLine(line),
+                None => Hint::Complete,
+            };
+        }
+        correct_dots = correct_dots.max(common);
+    }
+    Hint::WrongTurn { correct_dots }
+}
+
+/// Same hint as `find_hint`, but only searches solutions continuing the path,
+/// None if `cancel` was set before the search finished
+pub fn search_hint(puzzle: &Puzzle, dot_path: &[DotIndex], cancel: &AtomicBool) -> Option<Hint> {
+    let first_solution = |prefix: &[DotIndex]| {
+        let solver = PuzzleSolver::new(puzzle, Some(1))
+            .with_prefix(prefix)
+            .with_cancel(cancel);
+        let solution = solver.solve().pop();
+        (!cancel.load(Ordering::Relaxed)).then_some(solution)
+    };
+
+    let Some(solution) = first_solution(&[])? else {
+        return Some(Hint::NoSolution);
+    };
+    if dot_path.is_empty() {
+        return Some(Hint::NextLine(solution.line_path[0]));
+    }
+    if let Some(solution) = first_solution(dot_path)? {
+        return Some(match solution.line_path.get(dot_path.len() - 1) {
+            Some(&line) => Hint::NextLine(line),
+            None => Hint::Complete,
+        });
+    }
+
+    // shorter prefixes of a correct prefix are correct too, so the longest one is found by bisection
+    let (mut correct_dots, mut wrong_dots) = (0, dot_path.len());
+    while wrong_dots - correct_dots > 1 {
+        let middle = (correct_dots + wrong_dots) / 2;
+        if first_solution(&dot_path[..middle])?.is_some() {
+            correct_dots = middle;
+        } else {
+            wrong_dots = middle;
+        }
+    }
+    Some(Hint::WrongTurn { correct_dots })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_hint() {
+        let puzzle = Puzzle::default();
+        let solutions = find_solutions(&puzzle, None);
+        let solution = &solutions[0];
+
+        assert_eq!(
+            Hint::NextLine(solution.line_path[0]),
+            find_hint(&solutions, &solution.dot_path[..1])
+        );
+        assert_eq!(
+            Hint::NextLine(solution.line_path[2]),
+            find_hint(&solutions, &solution.dot_path[..3])
+        );
+        assert_eq!(Hint::Complete, find_hint(&solutions, &solution.dot_path));
+
+        let mut wrong_path = solution.dot_path.clone();
+        wrong_path.push(wrong_path[wrong_path.len() - 2]);
+        assert_eq!(
+            Hint::WrongTurn {
+                correct_dots: solution.dot_path.len()
+            },
+            find_hint(&solutions, &wrong_path)
+        );
+    }
+
+    #[test]
+    fn test_search_hint() {
+        let puzzle = Puzzle::default();
+        let solutions = find_solutions(&puzzle, None);
+        let solution = &solutions[0];
+        let mut wrong_path = solution.dot_path[..3].to_vec();
+        wrong_path.push(wrong_path[0]);
+        let paths = [
+            &[][..],
+            &solution.dot_path[..1],
+            &solution.dot_path[..3],
+            &solution.dot_path,
+            &wrong_path,
+        ];
+
+        let cancel = AtomicBool::new(false);
+        for path in paths {
+            assert_eq!(Some(find_hint(&solutions, path)), search_hint(&puzzle, path, &cancel));
+        }
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(None, search_hint(&puzzle, &solution.dot_path, &cancel));
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+/// What player wants to do with the line, independent of input device
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputIntent {
+    /// Start from start dot near position, if there is one
+    StartNear(Dot),
+    StartAt(DotIndex),
+    /// Move `distance` along direction, direction doesn't need to be normalized
+    MoveDirection(Dot, f32),
+    MoveDelta(Dot),
+    /// Move toward absolute position, for touch and absolute pointer
+    MoveTowards(Dot),
+    /// Go back to previous dot of the path
+    Undo,
+    /// Cut path back to the dot
+    RetractTo(DotIndex),
+    /// Cut path back to the dot of the path near position
+    RetractNear(Dot),
+    Submit,
+    /// Stop drawing without checking the path
+    Abandon,
+}
+
+impl PuzzleSolutionManager {
+    /// Error means inner state of manager is broken, see `update_mouse`
+    pub fn apply(&mut self, intent: InputIntent) -> Result<(), ManagerError> {
+        match intent {
+            InputIntent::StartNear(pos) => {
+                if !self.is_solving() {
+                    self.click(pos);
+                }
+            }
+            InputIntent::StartAt(start_dot) => {
+                self.start_at(start_dot);
+            }
+            InputIntent::MoveDirection(direction, distance) => {
+                if direction.length2() > 0.0 {
+                    self.update_mouse(direction.scale(distance / direction.length()))?;
+                }
+            }
+            InputIntent::MoveDelta(delta) => self.update_mouse(delta)?,
+            InputIntent::MoveTowards(target) => self.move_towards(target)?,
+            InputIntent::Undo => self.undo(),
+            InputIntent::RetractTo(dot) => {
+                self.retract_to(dot);
+            }
+            InputIntent::RetractNear(pos) => {
+                self.retract_near(pos);
+            }
+            InputIntent::Submit => {
+                self.submit();
+            }
+            InputIntent::Abandon => self.abandon(),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply_intents() {
+        let puzzle = Puzzle::default();
+        let start_dot = puzzle.start_dots[0];
+        let mut manager = PuzzleSolutionManager::new(&puzzle);
+
+        manager.apply(InputIntent::StartNear(Dot::new(0.5, 0.5))).unwrap();
+        assert!(!manager.is_solving());
+        manager.apply(InputIntent::StartAt(start_dot)).unwrap();
+        assert!(manager.is_solving());
+
+        for _ in 0..20 {
+            manager.apply(InputIntent::MoveDirection(Dot::new(0.0, 5.0), 0.01)).unwrap();
+        }
+        assert_eq!(2, manager.dot_path().len());
+        assert!(!manager.now_at_dot());
+        manager.apply(InputIntent::Undo).unwrap();
+        assert_eq!(2, manager.dot_path().len());
+        assert!(manager.now_at_dot());
+        manager.apply(InputIntent::Undo).unwrap();
+        assert_eq!(&vec![start_dot], manager.dot_path());
+
+        manager.apply(InputIntent::Abandon).unwrap();
+        assert!(!manager.is_solving());
+        assert!(!manager.is_drawing_solution());
+    }
+}
@@ -0,0 +1,112 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    Dot(DotIndex, DotComplexity),
+    Line(LineIndex, LineComplexity),
+    Pane(PaneIndex, PaneComplexity),
+}
+
+pub struct MinimizationReport {
+    pub puzzle: Puzzle,
+    pub essential: Vec<Symbol>,
+    pub removed: Vec<Symbol>,
+}
+
+impl Symbol {
+    fn remove_from(&self, puzzle: &mut Puzzle) {
+        match *self {
+            Symbol::Dot(key, _) => {
+                puzzle.dot_complexity.remove(&key);
+            }
+            Symbol::Line(key, _) => {
+                puzzle.line_complexity.remove(&key);
+            }
+            Symbol::Pane(key, _) => {
+                puzzle.pane_complexity.remove(&key);
+            }
+        }
+    }
+    fn insert_into(&self, puzzle: &mut Puzzle) {
+        match *self {
+            Symbol::Dot(key, value) => {
+                puzzle.dot_complexity.insert(key, value);
+            }
+            Symbol::Line(key, value) => {
+                puzzle.line_complexity.insert(key, value);
+            }
+            Symbol::Pane(key, value) => {
+                puzzle.pane_complexity.insert(key, value);
+            }
+        }
+    }
+}
+
+/// All symbols of puzzle, sorted so the result doesn't depend on HashMap order
+pub fn puzzle_symbols(puzzle: &Puzzle) -> Vec<Symbol> {
+    let mut dots: Vec<_> = puzzle.dot_complexity.iter().map(|(&k, &v)| (k, v)).collect();
+    let mut lines: Vec<_> = puzzle.line_complexity.iter().map(|(&k, &v)| (k, v)).collect();
+    let mut panes: Vec<_> = puzzle.pane_complexity.iter().map(|(&k, &v)| (k, v)).collect();
+    dots.sort_by_key(|(k, _)| *k);
+    lines.sort_by_key(|(k, _)| *k);
+    panes.sort_by_key(|(k, _)| *k);
+
+    let dots = dots.into_iter().map(|(k, v)| Symbol::Dot(k, v));
+    let lines = lines.into_iter().map(|(k, v)| Symbol::Line(k, v));
+    let panes = panes.into_iter().map(|(k, v)| Symbol::Pane(k, v));
+    dots.chain(lines).chain(panes).collect()
+}
+
+/// Greedily removes symbols while solution set stays the same
+pub fn minimize_puzzle(puzzle: &Puzzle) -> MinimizationReport {
+    let mut puzzle = puzzle.clone();
+    // removing symbol never removes solutions, so equal count means equal set
+    let solutions_count = find_solutions(&puzzle, None).len();
+
+    let mut essential = Vec::new();
+    let mut removed = Vec::new();
+    for symbol in puzzle_symbols(&puzzle) {
+        symbol.remove_from(&mut puzzle);
+        let new_count = find_solutions(&puzzle, Some(solutions_count + 1)).len();
+        if new_count == solutions_count {
+            removed.push(symbol);
+        } else {
+            symbol.insert_into(&mut puzzle);
+            essential.push(symbol);
+        }
+    }
+
+    MinimizationReport {
+        puzzle,
+        essential,
+        removed,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_minimize_keeps_solutions() {
+        let mut puzzle = Puzzle::default();
+        // every path goes through start dot, so this hexagon is useless
+        let start_dot = puzzle.start_dots[0];
+        puzzle.dot_complexity.insert(start_dot, DotComplexity::BlackHexagon);
+
+        let report = minimize_puzzle(&puzzle);
+        assert_eq!(
+            puzzle_symbols(&puzzle).len(),
+            report.essential.len() + report.removed.len()
+        );
+        assert!(
+            report
+                .removed
+                .contains(&Symbol::Dot(start_dot, DotComplexity::BlackHexagon))
+        );
+
+        let before = find_solutions(&puzzle, None);
+        let after = find_solutions(&report.puzzle, None);
+        assert_eq!(before, after);
+    }
+}
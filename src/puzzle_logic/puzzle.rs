@@ -1,8 +1,12 @@
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::ops::{Add, Sub};
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use serde::{Deserialize, Serialize};
+use super::STYLE_PRESETS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub struct Dot {
     pub x: f32,
     pub y: f32,
@@ -46,13 +50,13 @@ impl Sub for Dot {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct DotIndex(pub u16);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct LineIndex(pub DotIndex, pub DotIndex);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct PaneIndex(pub u16);
 
 impl Display for DotIndex {
@@ -81,20 +85,24 @@ impl LineIndex {
     pub fn get1(&self) -> DotIndex {
         self.1
     }
+    /// Returns the other end of the line, `dot` must be one of the ends
+    pub fn other(&self, dot: DotIndex) -> DotIndex {
+        if self.0 == dot { self.1 } else { self.0 }
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DotComplexity {
     BlackHexagon,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LineComplexity {
     BlackHexagon,
     LineBreak,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PaneComplexity {
     Square(ComplexityColor),
     // Star(Color)
@@ -103,7 +111,7 @@ pub enum PaneComplexity {
     // Jack
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, enum_iterator::Sequence, Serialize, Deserialize)]
 pub enum ComplexityColor {
     Black,
     White,
@@ -111,6 +119,7 @@ pub enum ComplexityColor {
 
 use eframe::egui::Color32;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Puzzle {
     pub dots: Vec<Dot>,
     pub lines: Vec<LineIndex>,
@@ -132,29 +141,32 @@ pub struct Puzzle {
     pub solution_color: Color32,
 }
 
-impl Default for Puzzle {
-    fn default() -> Self {
-        let n: usize = 5;
+impl Puzzle {
+    /// Grid of `width` columns and `height` rows of dots, without symbols, starts and ends
+    pub fn grid(width: usize, height: usize) -> Self {
+        let n = width.max(height);
         let padding = 1.0;
-        let endline_length = 0.5;
         let size = padding * 2.0 + (n - 1) as f32;
+        // smaller side is centered
+        let padding_x = padding + (n - width) as f32 / 2.0;
+        let padding_y = padding + (n - height) as f32 / 2.0;
 
         let cell_size = 1.0 / size;
 
         let mut dots: Vec<Dot> = Vec::new();
 
         let mut dots_indexes: Vec<Vec<DotIndex>> = Vec::new();
-        (0..n).for_each(|_| dots_indexes.push(Vec::new()));
+        (0..width).for_each(|_| dots_indexes.push(Vec::new()));
 
         {
             // create dots
             let mut i = 0;
-            for x in 0..n {
-                for y in 0..n {
-                    let x_float = (padding + x as f32) / size;
-                    let y_float = (padding + y as f32) / size;
+            for (x, column) in dots_indexes.iter_mut().enumerate() {
+                for y in 0..height {
+                    let x_float = (padding_x + x as f32) / size;
+                    let y_float = (padding_y + y as f32) / size;
                     dots.push(Dot::new(x_float, y_float));
-                    dots_indexes[x].push(DotIndex(i));
+                    column.push(DotIndex(i));
                     i += 1;
                 }
             }
@@ -163,43 +175,43 @@ impl Default for Puzzle {
         // create lines
         let mut lines: Vec<LineIndex> = Vec::new();
         let mut horizontal_lines: Vec<Vec<LineIndex>> = Vec::new();
-        horizontal_lines.resize(n, Vec::new());
+        horizontal_lines.resize(height, Vec::new());
         let mut vertical_lines: Vec<Vec<LineIndex>> = Vec::new();
-        vertical_lines.resize(n - 1, Vec::new());
+        vertical_lines.resize(height - 1, Vec::new());
         {
             // horizontal lines
-            for x in 0..(n - 1) {
-                for y in 0..n {
+            for x in 0..(width - 1) {
+                for (y, row) in horizontal_lines.iter_mut().enumerate() {
                     let dot1 = dots_indexes[x][y];
                     let dot2 = dots_indexes[x + 1][y];
                     let line = LineIndex(dot1, dot2);
                     lines.push(line);
-                    horizontal_lines[y].push(line);
+                    row.push(line);
                 }
             }
             // vertical lines
-            for y in 0..(n - 1) {
-                for x in 0..n {
-                    let dot1 = dots_indexes[x][y];
-                    let dot2 = dots_indexes[x][y + 1];
+            for (y, row) in vertical_lines.iter_mut().enumerate() {
+                for column in &dots_indexes {
+                    let dot1 = column[y];
+                    let dot2 = column[y + 1];
                     let line = LineIndex(dot1, dot2);
                     lines.push(line);
-                    vertical_lines[y].push(line);
+                    row.push(line);
                 }
             }
         }
 
         let mut panes: Vec<Dot> = Vec::new();
         let mut pane_nears: Vec<Vec<(LineIndex, PaneIndex)>> = Vec::new();
-        let m = n - 1;
-        pane_nears.resize(m * m, Vec::new());
-        for y in 0..m {
-            for x in 0..m {
-                let ind = y * m + x;
+        let (m_x, m_y) = (width - 1, height - 1);
+        pane_nears.resize(m_x * m_y, Vec::new());
+        for y in 0..m_y {
+            for x in 0..m_x {
+                let ind = y * m_x + x;
                 let vec = &mut pane_nears[ind];
                 {
-                    let x = (padding + (x as f32) + 0.5) / size;
-                    let y = (padding + (y as f32) + 0.5) / size;
+                    let x = (padding_x + (x as f32) + 0.5) / size;
+                    let y = (padding_y + (y as f32) + 0.5) / size;
                     panes.push(Dot { x, y });
                 }
 
@@ -208,63 +220,174 @@ impl Default for Puzzle {
                     vec.push((vertical_lines[y][x], PaneIndex(ind_near as u16)));
                 }
                 if y > 0 {
-                    let ind_near = ind - m; // down
+                    let ind_near = ind - m_x; // down
                     vec.push((horizontal_lines[y][x], PaneIndex(ind_near as u16)));
                 }
-                if x + 1 < m {
+                if x + 1 < m_x {
                     let ind_near = ind + 1; // right
-                    vec.push((vertical_lines[y][x+1], PaneIndex(ind_near as u16)));
+                    vec.push((vertical_lines[y][x + 1], PaneIndex(ind_near as u16)));
                 }
-                if y + 1 < m {
-                    let ind_near = ind + m; // up
-                    vec.push((horizontal_lines[y+1][x], PaneIndex(ind_near as u16)));
+                if y + 1 < m_y {
+                    let ind_near = ind + m_x; // up
+                    vec.push((horizontal_lines[y + 1][x], PaneIndex(ind_near as u16)));
                 }
             }
         }
 
-        let start_dots: Vec<DotIndex> = vec![dots_indexes[0][0]];
+        let (_, style) = STYLE_PRESETS[0];
+        Puzzle {
+            dots,
+            lines,
+            panes,
+            cell_size,
+            pane_nears,
+            start_dots: Vec::new(),
+            end_dots: Vec::new(),
+            dot_complexity: HashMap::new(),
+            line_complexity: HashMap::new(),
+            pane_complexity: HashMap::new(),
+
+            line_width: style.line_width,
+            background_color: style.background_color,
+            puzzle_color: style.puzzle_color,
+            solution_color: style.solution_color,
+        }
+    }
+}
 
-        let end_dots: Vec<DotIndex> = {
-            let end_dot = Dot {
-                x: (size - padding + endline_length) / size,
-                y: (size - padding) / size,
-            };
-            dots.push(end_dot);
-            let end_dot = DotIndex((dots.len() - 1) as u16);
+impl Puzzle {
+    /// Grid with start in the first corner and end next to the opposite one, without symbols
+    pub fn playable_grid(width: usize, height: usize) -> Self {
+        let mut puzzle = Puzzle::grid(width, height);
+        puzzle.start_dots.push(DotIndex(0));
+        puzzle
+            .add_end_stub(DotIndex((width * height - 1) as u16), Dot::new(1.0, 0.0))
+            .expect("end stub goes outside of the grid");
+        puzzle
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        // every dot and line stays on one line
+        let config = ron::ser::PrettyConfig::new().depth_limit(2);
+        ron::ser::to_string_pretty(self, config)
+    }
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Hash of layout and symbols, style is left out, so recolored puzzle stays the same.
+    /// It is saved with solver state, so it uses `FnvHasher` that doesn't change between builds
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        for dot in &self.dots {
+            (dot.x.to_bits(), dot.y.to_bits()).hash(&mut hasher);
+        }
+        (&self.lines, &self.start_dots, &self.end_dots).hash(&mut hasher);
+        hash_sorted(&self.dot_complexity, &mut hasher);
+        hash_sorted(&self.line_complexity, &mut hasher);
+        hash_sorted(&self.pane_complexity, &mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Order of `HashMap` is random, so entries are hashed by key order
+fn hash_sorted<K: Ord + Hash, V: Hash>(map: &HashMap<K, V>, hasher: &mut FnvHasher) {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|&(key, _)| key);
+    entries.hash(hasher);
+}
+
+/// 64-bit FNV-1a, see <http://www.isthe.com/chongo/tech/comp/fnv/>.
+/// `DefaultHasher` may change between Rust releases, this one is fixed.
+/// Integers are written as little-endian `u64`, so result doesn't depend on platform either
+struct FnvHasher(u64);
+
+impl FnvHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+}
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
 
-            lines.push(LineIndex(end_dot, dots_indexes[n - 1][n - 1]));
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(Self::PRIME);
+        }
+    }
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+    fn write_i8(&mut self, i: i8) {
+        self.write_i64(i as i64);
+    }
+    fn write_i16(&mut self, i: i16) {
+        self.write_i64(i as i64);
+    }
+    fn write_i32(&mut self, i: i32) {
+        self.write_i64(i as i64);
+    }
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
 
-            vec![end_dot]
-        };
+impl Default for Puzzle {
+    fn default() -> Self {
+        let mut puzzle = Puzzle::playable_grid(5, 5);
 
-        let mut dot_complexity = HashMap::new();
-        dot_complexity.insert(DotIndex(2), DotComplexity::BlackHexagon);
+        puzzle.dot_complexity.insert(DotIndex(2), DotComplexity::BlackHexagon);
 
-        let mut line_complexity = HashMap::new();
-        line_complexity.insert(lines[5], LineComplexity::BlackHexagon);
-        line_complexity.insert(lines[8], LineComplexity::LineBreak);
+        let lines = puzzle.lines.clone();
+        puzzle.line_complexity.insert(lines[5], LineComplexity::BlackHexagon);
+        puzzle.line_complexity.insert(lines[8], LineComplexity::LineBreak);
 
-        let mut pane_complexity = HashMap::new();
+        let pane_complexity = &mut puzzle.pane_complexity;
         pane_complexity.insert(PaneIndex(3), PaneComplexity::Square(ComplexityColor::Black));
         pane_complexity.insert(PaneIndex(4), PaneComplexity::Square(ComplexityColor::White));
 
-        Puzzle {
-            dots,
-            lines,
-            panes,
-            cell_size,
-            pane_nears,
-            start_dots,
-            end_dots,
-            dot_complexity,
-            line_complexity,
-            pane_complexity,
-
-            line_width: 0.035,
-            background_color: Color32::from_rgb(228, 165, 0),
-            puzzle_color: Color32::from_rgb(61, 46, 3),
-            // solution_color: Color32::from_rgb(255, 234, 84),
-            solution_color: Color32::from_rgb(255, 255, 255),
-        }
+        puzzle
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fingerprint_is_fixed() {
+        // test vector of FNV-1a
+        let mut hasher = FnvHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let puzzle = Puzzle::default();
+        // saved solver states rely on this value
+        assert_eq!(puzzle.fingerprint(), 0x1e3a_45ee_b9ad_5ca3);
+        let mut recolored = puzzle.clone();
+        recolored.set_style(STYLE_PRESETS[1].1);
+        assert_eq!(recolored.fingerprint(), puzzle.fingerprint());
     }
 }
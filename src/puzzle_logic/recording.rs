@@ -0,0 +1,123 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+/// Intents applied in one frame, `dt` includes skipped frames without intents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub dt: f32,
+    pub intents: Vec<InputIntent>,
+}
+
+/// State of manager after replay, compared in fixture tests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayResult {
+    pub dot_path: Vec<DotIndex>,
+    pub line_path: Vec<LineIndex>,
+    pub now_at_dot: bool,
+    pub is_solving: bool,
+    pub events: Vec<SolutionEvent>,
+}
+
+/// Input fed to `PuzzleSolutionManager` together with puzzle, replays give the same path
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub puzzle: Puzzle,
+    pub frames: Vec<RecordedFrame>,
+    /// What replay must give, `None` for new recordings
+    pub expected: Option<ReplayResult>,
+    #[serde(skip)]
+    pending_dt: f32,
+}
+
+impl Recording {
+    pub fn new(puzzle: &Puzzle) -> Self {
+        Self {
+            puzzle: puzzle.clone(),
+            frames: Vec::new(),
+            expected: None,
+            pending_dt: 0.0,
+        }
+    }
+
+    pub fn push_frame(&mut self, dt: f32, intents: &[InputIntent]) {
+        self.pending_dt += dt;
+        if intents.is_empty() {
+            return;
+        }
+        self.frames.push(RecordedFrame {
+            dt: self.pending_dt,
+            intents: intents.to_vec(),
+        });
+        self.pending_dt = 0.0;
+    }
+
+    /// Applies every intent to new manager, stops at first error
+    pub fn replay(&self) -> Result<ReplayResult, ManagerError> {
+        let mut manager = PuzzleSolutionManager::new(&self.puzzle);
+        let events = manager.subscribe();
+        for frame in &self.frames {
+            for &intent in &frame.intents {
+                manager.apply(intent)?;
+            }
+        }
+        Ok(ReplayResult {
+            dot_path: manager.dot_path().clone(),
+            line_path: manager.line_path().clone(),
+            now_at_dot: manager.now_at_dot(),
+            is_solving: manager.is_solving(),
+            events: events.try_iter().collect(),
+        })
+    }
+
+    /// Stores replay result as expected one, to make a fixture from recording
+    pub fn bless(&mut self) -> Result<(), ManagerError> {
+        self.expected = Some(self.replay()?);
+        Ok(())
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        // deeper levels stay on one line to keep files short
+        let config = ron::ser::PrettyConfig::new().depth_limit(3);
+        ron::ser::to_string_pretty(self, config)
+    }
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let puzzle = Puzzle::default();
+        let mut recording = Recording::new(&puzzle);
+        recording.push_frame(0.1, &[InputIntent::StartAt(puzzle.start_dots[0])]);
+        recording.push_frame(0.1, &[]);
+        recording.push_frame(0.1, &[InputIntent::MoveDelta(Dot::new(0.3, 0.05))]);
+        assert_eq!(2, recording.frames.len());
+        assert!((recording.frames[1].dt - 0.2).abs() < 1e-6);
+        recording.bless().unwrap();
+
+        let loaded = Recording::from_ron(&recording.to_ron().unwrap()).unwrap();
+        assert_eq!(recording.frames, loaded.frames);
+        assert_eq!(recording.expected, loaded.expected);
+        assert_eq!(loaded.expected, Some(loaded.replay().unwrap()));
+    }
+
+    #[test]
+    fn test_replay_fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let recording = Recording::from_ron(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            let expected = recording.expected.as_ref().expect("fixture must have expected result");
+            assert_eq!(expected, &recording.replay().unwrap(), "{path:?}");
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+}
@@ -1,7 +1,8 @@
 use super::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SolutionError {
     Incomplete,
     DotError(DotIndex),
@@ -11,75 +12,83 @@ pub enum SolutionError {
 
 struct SolutionChecker<'a> {
     puzzle: &'a Puzzle,
-    dot_path: &'a Vec<DotIndex>,
-    line_path: &'a Vec<LineIndex>,
+    dot_path: &'a [DotIndex],
+    line_path: &'a [LineIndex],
     components: Vec<Vec<PaneIndex>>,
 }
 
-pub fn check_solution<'a>(solution: &'a PuzzleSolutionManager<'a>) -> Result<(), SolutionError> {
-    let checker = SolutionChecker::new(solution)?;
-    checker.check_correctness()?;
-    checker.check_dots()?;
-    checker.check_lines()?;
-    checker.check_panes()?;
-    Ok(())
+pub fn check_solution(solution: &PuzzleSolutionManager) -> Result<(), SolutionError> {
+    if !solution.now_at_dot() {
+        return Err(SolutionError::Incomplete);
+    }
+    check_path(solution.puzzle(), solution.dot_path(), solution.line_path())
+}
+
+/// Checks finished path without `PuzzleSolutionManager`, used by solvers
+pub fn check_path(puzzle: &Puzzle, dot_path: &[DotIndex], line_path: &[LineIndex]) -> Result<(), SolutionError> {
+    match find_errors(puzzle, dot_path, line_path).into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Collects every broken rule instead of stopping at the first one
+pub fn find_errors(puzzle: &Puzzle, dot_path: &[DotIndex], line_path: &[LineIndex]) -> Vec<SolutionError> {
+    let checker = SolutionChecker::new(puzzle, dot_path, line_path);
+    let mut errors = Vec::new();
+    checker.check_correctness(&mut errors);
+    checker.check_dots(&mut errors);
+    checker.check_lines(&mut errors);
+    checker.check_panes(&mut errors);
+    errors
 }
 
 impl<'a> SolutionChecker<'a> {
-    fn new(solution: &'a PuzzleSolutionManager<'a>) -> Result<Self, SolutionError> {
-        if !solution.now_at_dot() {
-            return Err(SolutionError::Incomplete);
+    fn new(puzzle: &'a Puzzle, dot_path: &'a [DotIndex], line_path: &'a [LineIndex]) -> Self {
+        SolutionChecker {
+            puzzle,
+            dot_path,
+            line_path,
+            components: find_components(puzzle, line_path),
         }
-        Ok(SolutionChecker {
-            puzzle: solution.puzzle(),
-            dot_path: solution.dot_path(),
-            line_path: solution.line_path(),
-            components: find_components(solution.puzzle(), solution.line_path()),
-        })
     }
-    fn check_correctness(&self) -> Result<(), SolutionError> {
-        if self.dot_path.is_empty() {
-            panic!("Unreachable: dot_path is empty")
-        }
-        let end_dot = self.dot_path.last().expect("Unreachable");
-        if self.puzzle.end_dots.contains(end_dot) {
-            Ok(())
-        } else {
-            Err(SolutionError::Incomplete)
+    /// Empty path is incomplete too, it can come from outside of manager
+    fn check_correctness(&self, errors: &mut Vec<SolutionError>) {
+        let is_at_end = (self.dot_path.last()).is_some_and(|end_dot| self.puzzle.end_dots.contains(end_dot));
+        if !is_at_end {
+            errors.push(SolutionError::Incomplete);
         }
     }
-    fn check_dots(&self) -> Result<(), SolutionError> {
+    fn check_dots(&self, errors: &mut Vec<SolutionError>) {
         let map = &self.puzzle.dot_complexity;
         for (dot_index, dot_complexity) in map {
             match dot_complexity {
                 DotComplexity::BlackHexagon => {
                     if !self.dot_path.contains(dot_index) {
-                        return Err(SolutionError::DotError(*dot_index));
+                        errors.push(SolutionError::DotError(*dot_index));
                     }
                 }
             }
         }
-        Ok(())
     }
-    fn check_lines(&self) -> Result<(), SolutionError> {
+    fn check_lines(&self, errors: &mut Vec<SolutionError>) {
         let map = &self.puzzle.line_complexity;
         for (line_index, line_complexity) in map {
             match line_complexity {
                 LineComplexity::BlackHexagon => {
                     if !self.line_path.contains(line_index) {
-                        return Err(SolutionError::LineError(*line_index));
+                        errors.push(SolutionError::LineError(*line_index));
                     }
                 }
                 LineComplexity::LineBreak => {
                     if self.line_path.contains(line_index) {
-                        return Err(SolutionError::LineError(*line_index));
+                        errors.push(SolutionError::LineError(*line_index));
                     }
                 }
             }
         }
-        Ok(())
     }
-    fn check_panes(&self) -> Result<(), SolutionError> {
+    fn check_panes(&self, errors: &mut Vec<SolutionError>) {
         let map = &self.puzzle.pane_complexity;
         for component in &self.components {
             let component_complexity: Vec<&PaneComplexity> =
@@ -95,14 +104,14 @@ impl<'a> SolutionChecker<'a> {
                 }
             }
             if squares.keys().count() > 1 {
-                return Err(SolutionError::PaneError);
+                errors.push(SolutionError::PaneError);
             }
         }
-        Ok(())
     }
 }
 
-fn find_components(puzzle: &Puzzle, line_path: &[LineIndex]) -> Vec<Vec<PaneIndex>> {
+/// Panes split into regions by the path
+pub fn find_components(puzzle: &Puzzle, line_path: &[LineIndex]) -> Vec<Vec<PaneIndex>> {
     let n = puzzle.pane_nears.len();
     let mut color: Vec<i32> = vec![0; n]; // 0
 
@@ -147,4 +156,10 @@ mod test {
         assert_eq!(1, vecs.len());
         assert_eq!(puzzle.panes.len(), vecs[0].len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_empty_path_is_incomplete() {
+        let puzzle = Puzzle::default();
+        assert_eq!(Err(SolutionError::Incomplete), check_path(&puzzle, &[], &[]));
+    }
+}
@@ -1,48 +1,89 @@
 use super::*;
-use std::cmp;
-
-const DOT_LEAVE_RADIUS: f32 = 1.0;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound of segments passed in one `update_mouse`, protects from endless sliding
+const MAX_MOVE_STEPS: usize = 1000;
+/// Line from dot counts as "in pointer direction" when cosine of angle is bigger
+const CORNER_MIN_COS: f32 = 0.7;
+/// Line progress this close to the end counts as reaching the dot, hides float errors
+const DOT_SNAP_PROGRESS: f32 = 1e-4;
+/// How close to a dot position must be to pick it, in puzzle coords
+const DOT_PICK_RADIUS: f32 = 0.045;
+
+/// Misuse of manager or broken inner state, manager is never left half-moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagerError {
+    NotSolving,
+    NotDrawing,
+    NotStartDot(DotIndex),
+    /// Operation needs path to end on a line
+    AtDot,
+    /// Operation needs path to end on a dot
+    OnLine,
+    DotNotOnLine(DotIndex, LineIndex),
+    LineNotFromDot(LineIndex, DotIndex),
+    EmptyPath,
+    /// Snapshot doesn't fit the puzzle
+    InvalidSnapshot,
+}
 
-fn compare_dots(dot0: Dot, dot1: Dot, dot2: Dot, delta: Dot) -> cmp::Ordering {
-    let dot1 = dot1 - dot0;
-    let dot2 = dot2 - dot0;
-    let angle0 = (delta.y).atan2(delta.x);
-    let pi = std::f32::consts::PI;
-    let mut angle1 = ((dot1.y).atan2(dot1.x) - angle0).abs();
-    if angle1 > pi {
-        angle1 = 2.0 * pi - angle1;
-    }
-    let mut angle2 = ((dot2.y).atan2(dot2.x) - angle0).abs();
-    if angle2 > pi {
-        angle2 = 2.0 * pi - angle2;
+impl std::fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManagerError::NotSolving => write!(f, "not solving now"),
+            ManagerError::NotDrawing => write!(f, "not drawing now"),
+            ManagerError::NotStartDot(dot) => write!(f, "dot {dot} is not a start dot"),
+            ManagerError::AtDot => write!(f, "path ends on a dot, not on a line"),
+            ManagerError::OnLine => write!(f, "path ends on a line, not on a dot"),
+            ManagerError::DotNotOnLine(dot, line) => write!(f, "dot {dot} is not an end of {line}"),
+            ManagerError::LineNotFromDot(line, dot) => write!(f, "{line} doesn't start from dot {dot}"),
+            ManagerError::EmptyPath => write!(f, "path is empty"),
+            ManagerError::InvalidSnapshot => write!(f, "snapshot doesn't fit the puzzle"),
+        }
     }
-    angle1.partial_cmp(&angle2).expect("don't expect lines to degenerate")
 }
 
-pub struct PuzzleSolutionManager<'a> {
-    puzzle: &'a Puzzle,
+impl std::error::Error for ManagerError {}
+
+/// Whole drawing state of `PuzzleSolutionManager`, without puzzle and subscribers
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ManagerSnapshot {
+    /// `Puzzle::fingerprint` of the puzzle snapshot was taken on
+    pub puzzle_fingerprint: u64,
+    pub dot_path: Vec<DotIndex>,
+    pub line_path: Vec<LineIndex>,
+    pub now_at_dot: bool,
+    pub line_progress: f32,
+    pub is_solving: bool,
+    pub is_drawing_solution: bool,
+}
+
+/// Keeps own copy of puzzle, so puzzle can be edited while old trace is alive
+pub struct PuzzleSolutionManager {
+    puzzle: Puzzle,
     dot_path: Vec<DotIndex>,
     line_path: Vec<LineIndex>,
 
     now_at_dot: bool,
-    dot_pos: Dot, // in local coords
     line_progress: f32,
 
     is_solving: bool,
     is_drawing_solution: bool,
+
+    subscribers: EventSubscribers,
 }
 
-impl<'a> PuzzleSolutionManager<'a> {
-    pub fn new(puzzle: &'a Puzzle) -> Self {
+impl PuzzleSolutionManager {
+    pub fn new(puzzle: &Puzzle) -> Self {
         Self {
-            puzzle,
+            puzzle: puzzle.clone(),
             dot_path: Vec::new(),
             line_path: Vec::new(),
             now_at_dot: false,
-            dot_pos: Dot::ZERO,
             line_progress: 0.0,
             is_solving: false,
             is_drawing_solution: false,
+            subscribers: EventSubscribers::default(),
         }
     }
 
@@ -53,51 +94,22 @@ impl<'a> PuzzleSolutionManager<'a> {
         self.is_drawing_solution
     }
 
-    pub fn get_start_dot_dot_draw(&self) -> Dot {
+    pub fn get_start_dot_dot_draw(&self) -> Result<Dot, ManagerError> {
         if !self.is_drawing_solution {
-            panic!("not drawing now")
+            return Err(ManagerError::NotDrawing);
         }
-        self.get_dot(self.dot_path[0])
+        let start_dot = self.dot_path.first().ok_or(ManagerError::EmptyPath)?;
+        Ok(self.get_dot(*start_dot))
     }
-    pub fn get_lines_to_draw(&self) -> Vec<(Dot, Dot)> {
+    pub fn get_lines_to_draw(&self) -> Result<Vec<(Dot, Dot)>, ManagerError> {
         if !self.is_drawing_solution {
-            panic!("not drawing now")
+            return Err(ManagerError::NotDrawing);
         }
         let line_to_dots = |line: &LineIndex| -> (Dot, Dot) { (self.get_dot(line.0), self.get_dot(line.1)) };
         if self.now_at_dot {
-            let mut lines: Vec<_> = self.line_path.iter().map(line_to_dots).collect();
-
-            let near_line_option = self.get_near_line_on_dot(self.dot_pos);
-            if let Some(near_line) = near_line_option {
-                let dot_at = self.last_dot();
-                let mut proj = self.get_projection(near_line, self.dot_pos);
-                if dot_at == near_line.1 {
-                    proj += 1.0;
-                }
-
-                let dot1 = self.get_dot(near_line.0);
-                let dot2 = self.get_dot(near_line.1);
-                let dot_proj = dot1 + (dot2 - dot1).scale(proj);
-                if self.line_path.last() == Some(&near_line) {
-                    lines.pop();
-                    if self.last_dot() == near_line.1 {
-                        lines.push((dot1, dot_proj));
-                    } else {
-                        lines.push((dot_proj, dot2));
-                    }
-                } else if self.last_dot() == near_line.0 {
-                    lines.push((dot1, dot_proj));
-                } else {
-                    lines.push((dot_proj, dot2));
-                }
-            }
-
-            lines
+            Ok(self.line_path.iter().map(line_to_dots).collect())
         } else {
-            let (last_line, lines) = self
-                .line_path
-                .split_last()
-                .expect("line_path can't be empty while on line");
+            let (last_line, lines) = self.line_path.split_last().ok_or(ManagerError::EmptyPath)?;
 
             let mut lines: Vec<(Dot, Dot)> = lines.iter().map(line_to_dots).collect();
 
@@ -106,17 +118,17 @@ impl<'a> PuzzleSolutionManager<'a> {
 
             let dot_middle = dot1 + (dot2 - dot1).scale(self.line_progress);
 
-            if last_line.0 == self.last_dot() {
+            if last_line.0 == self.last_dot()? {
                 lines.push((dot1, dot_middle));
             } else {
                 lines.push((dot_middle, dot2));
             }
-            lines
+            Ok(lines)
         }
     }
 
-    pub fn puzzle(&self) -> &'a Puzzle {
-        self.puzzle
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
     }
     pub fn dot_path(&self) -> &Vec<DotIndex> {
         &self.dot_path
@@ -127,65 +139,117 @@ impl<'a> PuzzleSolutionManager<'a> {
     pub fn now_at_dot(&self) -> bool {
         self.now_at_dot
     }
+
+    pub fn snapshot(&self) -> ManagerSnapshot {
+        ManagerSnapshot {
+            puzzle_fingerprint: self.puzzle.fingerprint(),
+            dot_path: self.dot_path.clone(),
+            line_path: self.line_path.clone(),
+            now_at_dot: self.now_at_dot,
+            line_progress: self.line_progress,
+            is_solving: self.is_solving,
+            is_drawing_solution: self.is_drawing_solution,
+        }
+    }
+    /// Replaces drawing state, snapshot of other puzzle or which doesn't fit the puzzle is rejected without changes
+    pub fn restore(&mut self, snapshot: ManagerSnapshot) -> Result<(), ManagerError> {
+        if !self.is_valid_snapshot(&snapshot) {
+            return Err(ManagerError::InvalidSnapshot);
+        }
+        self.dot_path = snapshot.dot_path;
+        self.line_path = snapshot.line_path;
+        self.now_at_dot = snapshot.now_at_dot;
+        self.line_progress = snapshot.line_progress;
+        self.is_solving = snapshot.is_solving;
+        self.is_drawing_solution = snapshot.is_drawing_solution;
+        Ok(())
+    }
 }
 
-impl PuzzleSolutionManager<'_> {
+impl PuzzleSolutionManager {
     fn get_dot(&self, dot: DotIndex) -> Dot {
         self.puzzle.dots[dot.0 as usize]
     }
 
+    fn is_valid_snapshot(&self, snapshot: &ManagerSnapshot) -> bool {
+        let ManagerSnapshot { dot_path, line_path, now_at_dot, .. } = snapshot;
+        if snapshot.puzzle_fingerprint != self.puzzle.fingerprint() {
+            return false;
+        }
+        if !snapshot.is_drawing_solution {
+            return !snapshot.is_solving && dot_path.is_empty() && line_path.is_empty();
+        }
+        let Some(start_dot) = dot_path.first() else {
+            return false;
+        };
+        let lines_count = if *now_at_dot { dot_path.len() - 1 } else { dot_path.len() };
+        // checked first, lines are matched with dots by index below
+        if line_path.len() != lines_count {
+            return false;
+        }
+        let is_simple = (dot_path.iter()).all(|dot| dot_path.iter().filter(|x| *x == dot).count() == 1);
+        let lines_connect_dots = (line_path.iter().enumerate()).all(|(i, line)| {
+            let Some(&dot) = dot_path.get(i) else {
+                return false;
+            };
+            self.puzzle.lines.contains(line)
+                && line.contains(dot)
+                && dot_path.get(i + 1).is_none_or(|&next| line.other(dot) == next)
+        });
+        self.puzzle.start_dots.contains(start_dot)
+            && is_simple
+            && lines_connect_dots
+            && (0.0..=1.0).contains(&snapshot.line_progress)
+    }
+
     fn clear(&mut self) {
         self.dot_path.clear();
         self.line_path.clear();
         self.is_solving = false;
         self.is_drawing_solution = false;
     }
-    fn start_from(&mut self, start_dot: DotIndex) {
+    fn start_from(&mut self, start_dot: DotIndex) -> Result<(), ManagerError> {
         if !self.puzzle.start_dots.contains(&start_dot) {
-            panic!("start_dot {start_dot:?} doesn't exist in puzzle")
+            return Err(ManagerError::NotStartDot(start_dot));
         }
         self.clear();
         self.is_drawing_solution = true;
         self.is_solving = true;
         self.dot_path.push(start_dot);
         self.now_at_dot = true;
-        self.dot_pos = Dot::ZERO;
+        self.subscribers.send(SolutionEvent::Started(start_dot));
+        Ok(())
     }
 
-    fn move_to_dot(&mut self, dot: DotIndex) {
+    fn move_to_dot(&mut self, dot: DotIndex) -> Result<(), ManagerError> {
         if !self.is_solving {
-            panic!("not solving now");
+            return Err(ManagerError::NotSolving);
         }
-        if self.now_at_dot {
-            panic!("can't move from line to dot, while you at dot")
-        }
-
-        let last_line = self.last_line_while_at_line();
+        let last_line = self.last_line_while_at_line()?;
         if !last_line.contains(dot) {
-            panic!("can't move to dot {dot:?} from line {last_line:?}")
+            return Err(ManagerError::DotNotOnLine(dot, last_line));
         }
 
-        let last_dot = self.dot_path.last().expect("dot_path is never empty");
-        if dot == *last_dot {
+        if dot == self.last_dot()? {
             self.line_path.pop();
         } else {
             self.dot_path.push(dot);
         }
         self.now_at_dot = true;
-        self.dot_pos = Dot::ZERO;
+        Ok(())
     }
 
-    fn move_to_line(&mut self, line: LineIndex) {
+    fn move_to_line(&mut self, line: LineIndex) -> Result<(), ManagerError> {
         if !self.is_solving {
-            panic!("not solving now");
+            return Err(ManagerError::NotSolving);
         }
         if !self.now_at_dot {
-            panic!("can't move from dot to line, while you at line")
+            return Err(ManagerError::OnLine);
         }
 
-        let last_dot = self.last_dot();
+        let last_dot = self.last_dot()?;
         if !line.contains(last_dot) {
-            panic!("can't move to line {line:?} from dot {last_dot:?}")
+            return Err(ManagerError::LineNotFromDot(line, last_dot));
         }
 
         let last_line_option = self.line_path.last();
@@ -199,163 +263,538 @@ impl PuzzleSolutionManager<'_> {
             self.line_path.push(line);
         }
         self.now_at_dot = false;
+        Ok(())
     }
 
-    fn last_dot(&self) -> DotIndex {
-        *self.dot_path.last().expect("dot_path is never empty")
+    fn last_dot(&self) -> Result<DotIndex, ManagerError> {
+        self.dot_path.last().copied().ok_or(ManagerError::EmptyPath)
     }
-    fn last_line_while_at_line(&self) -> LineIndex {
+    fn last_line_while_at_line(&self) -> Result<LineIndex, ManagerError> {
         if self.now_at_dot {
-            panic!("don't use it while on dot");
+            return Err(ManagerError::AtDot);
         }
-        *self
-            .line_path
-            .last()
-            .expect("line_path can't be empty while now_at_dot==false")
+        self.line_path.last().copied().ok_or(ManagerError::EmptyPath)
     }
 
-    fn get_projection(&self, line: LineIndex, delta: Dot) -> f32 {
-        let dot = {
-            let dot1 = self.get_dot(line.0);
-            let dot2 = self.get_dot(line.1);
-            dot2 - dot1
-        };
+    /// Closest dot in pick radius of position
+    fn pick_dot(&self, dots: &[DotIndex], pos: Dot) -> Option<DotIndex> {
+        let dist = |dot: &DotIndex| (self.get_dot(*dot) - pos).length();
+        (dots.iter())
+            .filter(|dot| dist(dot) <= DOT_PICK_RADIUS)
+            .min_by(|dot1, dot2| dist(dot1).total_cmp(&dist(dot2)))
+            .copied()
+    }
 
-        delta.scalar(&dot) / dot.length2()
+    fn get_line_length(&self, line: LineIndex) -> f32 {
+        let dot1 = self.get_dot(line.0);
+        let dot2 = self.get_dot(line.1);
+        (dot1 - dot2).length()
+    }
+    fn get_direction(&self, from: DotIndex, line: LineIndex) -> Dot {
+        self.get_dot(line.other(from)) - self.get_dot(from)
     }
 
-    fn get_near_line_on_dot(&self, delta: Dot) -> Option<LineIndex> {
-        if !self.now_at_dot {
-            panic!("must be on dot");
+    /// Progress on current line counted from last dot of path
+    fn get_line_t(&self, from: DotIndex, line: LineIndex) -> f32 {
+        if line.0 == from {
+            self.line_progress
+        } else {
+            1.0 - self.line_progress
         }
+    }
+    fn set_line_t(&mut self, from: DotIndex, line: LineIndex, t: f32) {
+        self.line_progress = if line.0 == from { t } else { 1.0 - t };
+    }
+
+    /// How far path can go along line from dot, line breaks and path itself block it
+    fn get_max_line_t(&self, from: DotIndex, line: LineIndex) -> f32 {
+        let is_backtrack = self.now_at_dot && self.line_path.last() == Some(&line);
+        if is_backtrack {
+            return 1.0;
+        }
+        let line_length = self.get_line_length(line);
+        if self.puzzle.line_complexity.get(&line) == Some(&LineComplexity::LineBreak) {
+            LINE_BREAK_WIDTH - self.puzzle.line_width / line_length * 0.5
+        } else if self.dot_path.contains(&line.other(from)) {
+            1.0 - self.puzzle.line_width / line_length
+        } else {
+            1.0
+        }
+    }
+
+    /// Line from current dot which is closest to delta direction
+    fn choose_line_at_dot(&self, dot: DotIndex, delta: Dot) -> Option<LineIndex> {
+        let cos = |line: &LineIndex| {
+            let direction = self.get_direction(dot, *line);
+            direction.scalar(&delta) / direction.length() / delta.length()
+        };
+        (self.puzzle.lines.iter())
+            .filter(|line| line.contains(dot))
+            .filter(|line| cos(line) > 0.0 && self.get_max_line_t(dot, **line) > 0.0)
+            .max_by(|line1, line2| cos(line1).total_cmp(&cos(line2)))
+            .copied()
+    }
 
-        let dot_ind = self.last_dot();
-        let dot = self.get_dot(dot_ind);
+    /// Is there other line from dot in direction, used to slide toward corners
+    fn can_turn_at(&self, dot: DotIndex, except: LineIndex, direction: Dot) -> bool {
+        (self.puzzle.lines.iter())
+            .filter(|line| line.contains(dot) && **line != except)
+            .any(|line| {
+                let line_direction = self.get_direction(dot, *line);
+                line_direction.scalar(&direction) / line_direction.length() / direction.length() > CORNER_MIN_COS
+            })
+    }
 
-        let line_get_not = |line: LineIndex| DotIndex(line.0.0 + line.1.0 - dot_ind.0);
-        let (&near_line, _) = self
-            .puzzle
-            .lines
-            .iter()
-            .filter(|line| line.contains(dot_ind)) // get line from 'dot'
-            .map(|line| (line, self.get_dot(line_get_not(*line)))) // get second Dot
-            .min_by(|(_, dot1), (_, dot2)| compare_dots(dot, *dot1, *dot2, delta)) // get nearest to delta vector
-            .expect("dot {dot} don't have line from it");
+    /// Point of line from dot which is closest to target, as line progress from dot
+    fn project_on_line(&self, from: DotIndex, line: LineIndex, target: Dot) -> f32 {
+        let direction = self.get_direction(from, line);
+        let t = (target - self.get_dot(from)).scalar(&direction) / direction.length2();
+        t.clamp(0.0, self.get_max_line_t(from, line).max(0.0))
+    }
 
-        let scalar = {
-            let dot2 = self.get_dot(line_get_not(near_line));
-            (dot2 - dot).scalar(&delta)
+    /// Moves along current line toward target, returns true when path reached a dot and can go on
+    fn move_along_line_towards(&mut self, target: Dot) -> Result<bool, ManagerError> {
+        let line = self.last_line_while_at_line()?;
+        let from = self.last_dot()?;
+        let t = self.project_on_line(from, line, target);
+        if t >= 1.0 - DOT_SNAP_PROGRESS {
+            self.move_to_dot(line.other(from))?;
+            Ok(true)
+        } else if t <= DOT_SNAP_PROGRESS {
+            self.move_to_dot(from)?;
+            Ok(true)
+        } else {
+            self.set_line_t(from, line, t);
+            Ok(false)
+        }
+    }
+
+    /// Line from dot on which path gets closer to target than the dot itself
+    fn choose_line_towards(&self, dot: DotIndex, target: Dot) -> Option<LineIndex> {
+        let distance = |line: &LineIndex| {
+            let t = self.project_on_line(dot, *line, target);
+            let point = self.get_dot(dot) + self.get_direction(dot, *line).scale(t);
+            (point - target).length()
         };
-        if scalar > 0.0 { Some(near_line) } else { None }
+        let dot_distance = (self.get_dot(dot) - target).length();
+        (self.puzzle.lines.iter())
+            .filter(|line| line.contains(dot) && self.get_max_line_t(dot, **line) > 0.0)
+            .map(|line| (line, distance(line)))
+            .filter(|(_, line_distance)| *line_distance < dot_distance - f32::EPSILON)
+            .min_by(|(_, distance1), (_, distance2)| distance1.total_cmp(distance2))
+            .map(|(line, _)| *line)
     }
-    fn get_line_length(&self, line: LineIndex) -> f32 {
-        let dot1 = self.get_dot(line.0);
-        let dot2 = self.get_dot(line.1);
-        (dot1 - dot2).length()
+
+    /// Moves along current line, returns part of delta which is left after reaching a dot
+    fn move_along_line(&mut self, delta: Dot) -> Result<Dot, ManagerError> {
+        let line = self.last_line_while_at_line()?;
+        let from = self.last_dot()?;
+        let to = line.other(from);
+
+        let length = self.get_line_length(line);
+        let unit = self.get_direction(from, line).scale(1.0 / length);
+        let along = delta.scalar(&unit);
+        let across = delta - unit.scale(along);
+        let t = self.get_line_t(from, line);
+
+        let mut forward = along;
+        if across.length() > along.abs() {
+            // pointer goes sideways, slide to the end of line where path can turn that way
+            let slide = across.length();
+            let to_turn = self.can_turn_at(to, line, across);
+            let from_turn = t > 0.0 && self.can_turn_at(from, line, across);
+            forward += match (from_turn, to_turn) {
+                (true, true) if t < 0.5 => -slide,
+                (_, true) => slide,
+                (true, false) => -slide,
+                (false, false) => 0.0,
+            };
+        }
+        if forward.abs() < f32::EPSILON {
+            return Ok(Dot::ZERO);
+        }
+
+        let new_t = t + forward / length;
+        let max_t = self.get_max_line_t(from, line);
+        if new_t >= 1.0 - DOT_SNAP_PROGRESS && max_t >= 1.0 {
+            // delta which can't even leave the snapped dot is used up, otherwise it comes back forever
+            let used = if forward > 0.0 { ((1.0 - t) * length / forward).min(1.0) } else { 1.0 };
+            self.move_to_dot(to)?;
+            Ok(delta.scale(1.0 - used))
+        } else if new_t <= DOT_SNAP_PROGRESS {
+            let used = if forward < 0.0 { (t * length / -forward).min(1.0) } else { 1.0 };
+            self.move_to_dot(from)?;
+            Ok(delta.scale(1.0 - used))
+        } else {
+            self.set_line_t(from, line, new_t.min(max_t));
+            Ok(Dot::ZERO)
+        }
     }
 }
 
-impl PuzzleSolutionManager<'_> {
+impl PuzzleSolutionManager {
+    /// Every event after this call is sent to returned receiver
+    pub fn subscribe(&mut self) -> std::sync::mpsc::Receiver<SolutionEvent> {
+        self.subscribers.subscribe()
+    }
+
     /// returns 'is_solving'
     pub fn click(&mut self, mouse_pos: Dot) -> bool {
         if self.is_solving {
-            match check_solution(self) {
-                Ok(()) => {
-                    self.is_solving = false;
-                    println!("Correct solution!")
-                }
-                Err(err) => {
-                    self.clear();
-                    println!("Incorrect: {err:?}")
-                }
-            }
+            self.submit();
             false
         } else {
             // try start solving
-            for &dot_index in &self.puzzle.start_dots {
-                let dot = self.get_dot(dot_index);
-                let dist = (dot - mouse_pos).length();
-                let start_dot_radius = 0.045; // TODO: dont use magic value
-                if dist <= start_dot_radius {
-                    self.start_from(dot_index);
-                    return true;
-                }
+            match self.pick_dot(&self.puzzle.start_dots, mouse_pos) {
+                Some(start_dot) => self.start_from(start_dot).is_ok(),
+                None => false,
             }
-            false
         }
     }
 
-    pub fn update_mouse(&mut self, delta: Dot) {
-        if !self.is_solving {
-            return;
+    /// Starts solving from start dot without mouse, returns false if it's not a start dot
+    pub fn start_at(&mut self, start_dot: DotIndex) -> bool {
+        !self.is_solving && self.start_from(start_dot).is_ok()
+    }
+
+    /// Stops drawing without checking the path
+    pub fn abandon(&mut self) {
+        if self.is_solving {
+            self.clear();
+            self.subscribers.send(SolutionEvent::Abandoned);
         }
-        if delta.x.abs() < f32::EPSILON && delta.y.abs() < f32::EPSILON {
+    }
+
+    /// Goes back to previous dot, or to the dot line started from
+    pub fn undo(&mut self) {
+        if !self.is_solving {
             return;
         }
         if self.now_at_dot {
-            self.dot_pos = self.dot_pos + delta;
-
-            let dist = self.dot_pos.length();
-            let max_dist = self.puzzle.line_width * DOT_LEAVE_RADIUS;
-            if dist > max_dist {
-                let near_line_option = self.get_near_line_on_dot(delta);
-                if let Some(near_line) = near_line_option {
-                    let dot_ind = self.last_dot();
-                    self.move_to_line(near_line);
-                    self.line_progress = if near_line.0 == dot_ind { 0.0 } else { 1.0 };
-                    self.update_mouse(self.dot_pos);
-                }
+            if self.dot_path.len() < 2 {
+                return;
             }
-        } else {
-            let line = self.last_line_while_at_line();
-            let mut proj = self.get_projection(line, delta);
-
-            let has_line_break = self.puzzle.line_complexity.get(&line) == Some(&LineComplexity::LineBreak);
-            if has_line_break {
-                let line_length = self.get_line_length(line);
-                let max_progress = LINE_BREAK_WIDTH - self.puzzle.line_width / line_length * 0.5;
-                if self.line_progress < 0.5 {
-                    if self.line_progress + proj > max_progress {
-                        proj = max_progress - self.line_progress
-                    }
-                } else {
-                    if self.line_progress + proj < 1.0 - max_progress {
-                        proj = 1.0 - max_progress - self.line_progress
-                    }
-                }
+            self.dot_path.pop();
+        }
+        self.line_path.pop();
+        self.now_at_dot = true;
+    }
+
+    /// Cuts path back to dot of the path, returns false if dot isn't on the path
+    pub fn retract_to(&mut self, dot: DotIndex) -> bool {
+        if !self.is_solving {
+            return false;
+        }
+        let Some(index) = self.dot_path.iter().position(|&x| x == dot) else {
+            return false;
+        };
+        self.dot_path.truncate(index + 1);
+        self.line_path.truncate(index);
+        self.now_at_dot = true;
+        true
+    }
+
+    /// Cuts path back to dot of the path near position
+    pub fn retract_near(&mut self, pos: Dot) -> bool {
+        match self.pick_dot(&self.dot_path, pos) {
+            Some(dot) => self.retract_to(dot),
+            None => false,
+        }
+    }
+
+    /// Checks drawn path, wrong path is cleared
+    pub fn submit(&mut self) -> SubmitOutcome {
+        if !self.is_solving {
+            return SubmitOutcome::NotSolving;
+        }
+        match check_solution(self) {
+            Ok(()) => {
+                self.is_solving = false;
+                self.subscribers.send(SolutionEvent::Solved);
+                SubmitOutcome::Solved
             }
+            Err(err) => {
+                self.clear();
+                self.subscribers.send(SolutionEvent::Failed(err.clone()));
+                SubmitOutcome::Failed(err)
+            }
+        }
+    }
 
-            let dot1 = self.get_dot(line.0);
-            let dot2 = self.get_dot(line.1);
-            let dot_leave_progress = {
-                let line_length = (dot1 - dot2).length();
-                self.puzzle.line_width * 0.5 / line_length * DOT_LEAVE_RADIUS
-            };
+    /// Moves path head to the closest point to target which is reachable without going away from it
+    ///
+    /// Used when path follows absolute pointer position instead of deltas
+    pub fn move_towards(&mut self, target: Dot) -> Result<(), ManagerError> {
+        if !self.is_solving {
+            return Ok(());
+        }
+        for _ in 0..MAX_MOVE_STEPS {
+            if self.now_at_dot {
+                let dot = self.last_dot()?;
+                let Some(line) = self.choose_line_towards(dot, target) else {
+                    break;
+                };
+                self.move_to_line(line)?;
+                self.line_progress = if line.0 == dot { 0.0 } else { 1.0 };
+            } else if !self.move_along_line_towards(target)? {
+                break;
+            }
+        }
+        Ok(())
+    }
 
-            if -f32::EPSILON <= proj && proj <= f32::EPSILON {
-                return;
+    /// Moves path by pointer delta, can pass many dots and slides around corners
+    ///
+    /// Moving while not solving does nothing, error means inner state is broken
+    pub fn update_mouse(&mut self, delta: Dot) -> Result<(), ManagerError> {
+        if !self.is_solving {
+            return Ok(());
+        }
+        let mut delta = delta;
+        for _ in 0..MAX_MOVE_STEPS {
+            if delta.x.abs() < f32::EPSILON && delta.y.abs() < f32::EPSILON {
+                break;
             }
-            if proj > 0.0 {
-                if self.line_progress + proj > 1.0 - dot_leave_progress {
-                    self.move_to_dot(line.1);
-                    self.dot_pos = (dot1 - dot2).scale(dot_leave_progress);
-
-                    let scale = 1.0 - (1.0 - dot_leave_progress - self.line_progress) / proj;
-                    self.update_mouse(delta.scale(scale));
-                } else {
-                    self.line_progress += proj;
-                }
+            if self.now_at_dot {
+                let dot = self.last_dot()?;
+                let Some(line) = self.choose_line_at_dot(dot, delta) else {
+                    break;
+                };
+                self.move_to_line(line)?;
+                self.line_progress = if line.0 == dot { 0.0 } else { 1.0 };
             } else {
-                if self.line_progress + proj < dot_leave_progress {
-                    self.move_to_dot(line.0);
-                    self.dot_pos = (dot2 - dot1).scale(dot_leave_progress);
-
-                    let scale = 1.0 - (dot_leave_progress - self.line_progress) / proj;
-                    self.update_mouse(delta.scale(scale));
-                } else {
-                    self.line_progress += proj;
+                delta = self.move_along_line(delta)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::prelude::*;
+
+    fn start(puzzle: &Puzzle) -> PuzzleSolutionManager {
+        let mut manager = PuzzleSolutionManager::new(puzzle);
+        assert!(manager.start_at(puzzle.start_dots[0]));
+        manager
+    }
+    fn play(manager: &mut PuzzleSolutionManager, deltas: &[(f32, f32)]) {
+        for &(x, y) in deltas {
+            manager.update_mouse(Dot::new(x, y)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_large_delta_passes_many_dots() {
+        let puzzle = Puzzle::default();
+        let mut manager = start(&puzzle);
+        play(&mut manager, &[(0.45, 0.0)]);
+        assert_eq!(&vec![DotIndex(0), DotIndex(5), DotIndex(10)], manager.dot_path());
+        assert!(!manager.now_at_dot());
+
+        play(&mut manager, &[(-0.45, 0.0)]);
+        assert_eq!(&vec![DotIndex(0)], manager.dot_path());
+        assert!(manager.line_path().is_empty());
+
+        play(&mut manager, &[(100.0, 100.0), (-100.0, 30.0)]);
+        assert!(manager.dot_path().len() <= puzzle.dots.len());
+    }
+
+    #[test]
+    fn test_tiny_delta_is_used_up() {
+        let puzzle = Puzzle::default();
+        let mut manager = start(&puzzle);
+        manager.move_to_line(LineIndex(DotIndex(0), DotIndex(5))).unwrap();
+        manager.line_progress = 0.0;
+        assert_eq!(Dot::ZERO, manager.move_along_line(Dot::new(1e-6, 0.0)).unwrap());
+        assert!(manager.now_at_dot());
+        assert_eq!(&vec![DotIndex(0)], manager.dot_path());
+    }
+
+    #[test]
+    fn test_slide_around_corner() {
+        let puzzle = Puzzle::default();
+
+        // closer to next dot, path slides forward and turns up
+        let mut manager = start(&puzzle);
+        play(&mut manager, &[(0.1, 0.0), (0.0, 0.2)]);
+        assert_eq!(&vec![DotIndex(0), DotIndex(5)], manager.dot_path());
+        assert_eq!(Some(&LineIndex(DotIndex(5), DotIndex(6))), manager.line_path().last());
+
+        // closer to start, path slides back and turns up there
+        let mut manager = start(&puzzle);
+        play(&mut manager, &[(0.05, 0.0), (0.0, 0.2)]);
+        assert_eq!(&vec![DotIndex(0)], manager.dot_path());
+        assert_eq!(&vec![LineIndex(DotIndex(0), DotIndex(1))], manager.line_path());
+    }
+
+    #[test]
+    fn test_line_break_blocks() {
+        let mut puzzle = Puzzle::default();
+        puzzle
+            .line_complexity
+            .insert(puzzle.lines[0], LineComplexity::LineBreak);
+        let mut manager = start(&puzzle);
+        play(&mut manager, &[(0.5, 0.0), (0.5, 0.0)]);
+        assert_eq!(&vec![DotIndex(0)], manager.dot_path());
+        assert!(!manager.now_at_dot());
+    }
+
+    #[test]
+    fn test_path_cant_cross_itself() {
+        let puzzle = Puzzle::default();
+        let mut manager = start(&puzzle);
+        play(
+            &mut manager,
+            &[(0.18, 0.0), (0.0, 0.18), (-0.18, 0.0), (0.0, -0.18), (0.0, -0.18)],
+        );
+        let expected = vec![DotIndex(0), DotIndex(5), DotIndex(6), DotIndex(1)];
+        assert_eq!(&expected, manager.dot_path());
+        assert!(!manager.now_at_dot());
+    }
+
+    fn random_dot(rng: &mut StdRng, scale: f32) -> Dot {
+        Dot::new(rng.random_range(-scale..scale), rng.random_range(-scale..scale))
+    }
+    fn random_intent(rng: &mut StdRng, puzzle: &Puzzle) -> InputIntent {
+        match rng.random_range(0..10) {
+            0 => InputIntent::StartNear(random_dot(rng, 1.0)),
+            1 => InputIntent::StartAt(DotIndex(rng.random_range(0..puzzle.dots.len() as u16 + 2))),
+            2 => InputIntent::Undo,
+            3 => InputIntent::Submit,
+            4 => InputIntent::Abandon,
+            5 => InputIntent::MoveDirection(random_dot(rng, 1.0), rng.random_range(-0.2..1.0)),
+            6 => InputIntent::MoveDelta(random_dot(rng, 10.0)),
+            7 => InputIntent::MoveTowards(random_dot(rng, 1.0)),
+            _ => InputIntent::MoveDelta(random_dot(rng, 0.1)),
+        }
+    }
+
+    #[test]
+    fn test_random_input_never_breaks_state() {
+        let mut puzzle = Puzzle::default();
+        puzzle.start_dots.push(DotIndex(12));
+        let mut rng = StdRng::seed_from_u64(34);
+        for _ in 0..50 {
+            let mut manager = PuzzleSolutionManager::new(&puzzle);
+            for _ in 0..200 {
+                let intent = random_intent(&mut rng, &puzzle);
+                assert_eq!(Ok(()), manager.apply(intent), "{intent:?}");
+
+                assert_eq!(manager.is_drawing_solution(), manager.get_lines_to_draw().is_ok());
+                if manager.is_solving() {
+                    let lines_on_dots = manager.line_path().len() + manager.now_at_dot() as usize;
+                    assert_eq!(manager.dot_path().len(), lines_on_dots);
                 }
             }
         }
     }
+
+    #[test]
+    fn test_retract() {
+        let puzzle = Puzzle::default();
+        let mut manager = start(&puzzle);
+        play(&mut manager, &[(0.45, 0.0), (0.0, 0.1)]);
+        assert_eq!(&vec![DotIndex(0), DotIndex(5), DotIndex(10), DotIndex(15)], manager.dot_path());
+
+        assert!(!manager.retract_to(DotIndex(20)));
+        assert!(manager.retract_near(puzzle.dots[5] + Dot::new(0.01, 0.01)));
+        assert_eq!(&vec![DotIndex(0), DotIndex(5)], manager.dot_path());
+        assert_eq!(1, manager.line_path().len());
+        assert!(manager.now_at_dot());
+
+        assert!(manager.retract_to(DotIndex(0)));
+        assert!(manager.line_path().is_empty());
+        assert!(manager.is_solving());
+    }
+
+    #[test]
+    fn test_move_towards() {
+        let puzzle = Puzzle::default();
+        let mut manager = start(&puzzle);
+        manager.move_towards(Dot::new(0.45, 0.2)).unwrap();
+        assert_eq!(&vec![DotIndex(0), DotIndex(5)], manager.dot_path());
+        assert!(!manager.now_at_dot());
+        let lines = manager.get_lines_to_draw().unwrap();
+        assert!((lines[1].1.x - 0.45).abs() < 1e-4);
+
+        manager.move_towards(Dot::new(0.52, 0.49)).unwrap();
+        assert_eq!(&vec![DotIndex(0), DotIndex(5), DotIndex(10), DotIndex(11)], manager.dot_path());
+        assert!(!manager.now_at_dot());
+
+        // path goes around to the closest point, stopping before it touches itself
+        manager.move_towards(puzzle.dots[0]).unwrap();
+        let expected = vec![DotIndex(0), DotIndex(5), DotIndex(10), DotIndex(11), DotIndex(6), DotIndex(1)];
+        assert_eq!(&expected, manager.dot_path());
+        assert!(!manager.now_at_dot());
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let puzzle = Puzzle::default();
+        let mut manager = start(&puzzle);
+        play(&mut manager, &[(0.4, 0.0)]);
+        let snapshot = manager.snapshot();
+        let text = ron::to_string(&snapshot).unwrap();
+
+        let mut restored = PuzzleSolutionManager::new(&puzzle);
+        restored.restore(ron::from_str(&text).unwrap()).unwrap();
+        assert_eq!(snapshot, restored.snapshot());
+        play(&mut manager, &[(0.0, 0.3)]);
+        play(&mut restored, &[(0.0, 0.3)]);
+        assert_eq!(manager.snapshot(), restored.snapshot());
+
+        let mut broken = snapshot.clone();
+        broken.line_path.pop();
+        assert_eq!(Err(ManagerError::InvalidSnapshot), restored.restore(broken));
+        broken = snapshot.clone();
+        broken.dot_path[1] = DotIndex(6);
+        assert_eq!(Err(ManagerError::InvalidSnapshot), restored.restore(broken));
+        // more lines than dots, like in hand edited state file
+        broken = ManagerSnapshot {
+            dot_path: vec![DotIndex(0)],
+            line_path: vec![LineIndex(DotIndex(0), DotIndex(5)), LineIndex(DotIndex(5), DotIndex(10))],
+            now_at_dot: true,
+            ..snapshot
+        };
+        assert_eq!(Err(ManagerError::InvalidSnapshot), restored.restore(broken.clone()));
+        broken.now_at_dot = false;
+        assert_eq!(Err(ManagerError::InvalidSnapshot), restored.restore(broken));
+        assert_eq!(manager.snapshot(), restored.snapshot());
+
+        // path fits, but symbols are different
+        let mut other_puzzle = puzzle.clone();
+        other_puzzle.dot_complexity.clear();
+        let mut other = PuzzleSolutionManager::new(&other_puzzle);
+        assert_eq!(Err(ManagerError::InvalidSnapshot), other.restore(manager.snapshot()));
+        let mut recolored_puzzle = puzzle.clone();
+        recolored_puzzle.solution_color = eframe::egui::Color32::RED;
+        let mut recolored = PuzzleSolutionManager::new(&recolored_puzzle);
+        assert_eq!(Ok(()), recolored.restore(manager.snapshot()));
+
+        let empty = ManagerSnapshot {
+            puzzle_fingerprint: puzzle.fingerprint(),
+            ..ManagerSnapshot::default()
+        };
+        restored.restore(empty).unwrap();
+        assert!(!restored.is_drawing_solution());
+    }
+
+    #[test]
+    fn test_errors_instead_of_panics() {
+        let puzzle = Puzzle::default();
+        let mut manager = PuzzleSolutionManager::new(&puzzle);
+        assert_eq!(Err(ManagerError::NotDrawing), manager.get_lines_to_draw());
+        assert_eq!(Err(ManagerError::NotDrawing), manager.get_start_dot_dot_draw());
+        assert_eq!(Err(ManagerError::NotStartDot(DotIndex(1))), manager.start_from(DotIndex(1)));
+        assert_eq!(Err(ManagerError::NotSolving), manager.move_to_line(puzzle.lines[0]));
+
+        let mut manager = start(&puzzle);
+        assert_eq!(Err(ManagerError::AtDot), manager.move_to_dot(DotIndex(1)));
+        let far_line = LineIndex(DotIndex(23), DotIndex(24));
+        assert_eq!(Err(ManagerError::LineNotFromDot(far_line, DotIndex(0))), manager.move_to_line(far_line));
+        assert_eq!(&vec![DotIndex(0)], manager.dot_path());
+    }
 }
@@ -0,0 +1,328 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Solution {
+    pub dot_path: Vec<DotIndex>,
+    pub line_path: Vec<LineIndex>,
+}
+
+/// Counters collected while searching, used to estimate difficulty
+#[derive(Debug, Clone, Default)]
+pub struct SolverStats {
+    /// Number of visited search states (path prefixes), including the ones where branch was cut
+    pub nodes: usize,
+    /// Search states whose branch was cut, because no solution can continue them
+    pub pruned: usize,
+    /// Paths which reached an end dot, correct or not
+    pub complete_paths: usize,
+    /// Complete paths which break exactly one rule
+    pub near_solutions: usize,
+    /// For every dot: how many times search stood on it and how many moves it had there
+    pub dot_visits: Vec<usize>,
+    pub dot_choices: Vec<usize>,
+}
+
+/// Walks simple paths from start dots to end dots, cutting paths which can't be finished
+/// or can't reach some hexagon anymore
+pub struct PuzzleSolver<'a> {
+    puzzle: &'a Puzzle,
+    /// Lines with line break are left out
+    near_lines: Vec<Vec<LineIndex>>,
+    is_end: Vec<bool>,
+    hexagon_dots: Vec<DotIndex>,
+    hexagon_lines: Vec<LineIndex>,
+    limit: Option<usize>,
+    /// Search only continues this path, if it isn't empty
+    prefix: Vec<DotIndex>,
+    /// Search stops as soon as it's set, found solutions are returned as they are
+    cancel: Option<&'a AtomicBool>,
+
+    dot_path: Vec<DotIndex>,
+    line_path: Vec<LineIndex>,
+    visited: Vec<bool>,
+    /// Dots reachable from the end of the path, only valid inside `can_finish`
+    reachable: Vec<bool>,
+    found: usize,
+    /// Visitor asked to stop
+    is_stopped: bool,
+    stats: SolverStats,
+}
+
+pub fn find_solutions(puzzle: &Puzzle, limit: Option<usize>) -> Vec<Solution> {
+    PuzzleSolver::new(puzzle, limit).solve()
+}
+
+impl<'a> PuzzleSolver<'a> {
+    pub fn new(puzzle: &'a Puzzle, limit: Option<usize>) -> Self {
+        let mut near_lines = vec![Vec::new(); puzzle.dots.len()];
+        for &line in &puzzle.lines {
+            if puzzle.line_complexity.get(&line) == Some(&LineComplexity::LineBreak) {
+                continue;
+            }
+            near_lines[line.0.0 as usize].push(line);
+            near_lines[line.1.0 as usize].push(line);
+        }
+        let mut is_end = vec![false; puzzle.dots.len()];
+        for dot in &puzzle.end_dots {
+            is_end[dot.0 as usize] = true;
+        }
+        let mut hexagon_dots: Vec<DotIndex> = (puzzle.dot_complexity.iter())
+            .filter(|(_, complexity)| **complexity == DotComplexity::BlackHexagon)
+            .map(|(dot, _)| *dot)
+            .collect();
+        hexagon_dots.sort();
+        let mut hexagon_lines: Vec<LineIndex> = (puzzle.line_complexity.iter())
+            .filter(|(_, complexity)| **complexity == LineComplexity::BlackHexagon)
+            .map(|(line, _)| *line)
+            .collect();
+        hexagon_lines.sort();
+        Self {
+            puzzle,
+            near_lines,
+            is_end,
+            hexagon_dots,
+            hexagon_lines,
+            limit,
+            prefix: Vec::new(),
+            cancel: None,
+            dot_path: Vec::new(),
+            line_path: Vec::new(),
+            visited: vec![false; puzzle.dots.len()],
+            reachable: vec![false; puzzle.dots.len()],
+            found: 0,
+            is_stopped: false,
+            stats: SolverStats {
+                dot_visits: vec![0; puzzle.dots.len()],
+                dot_choices: vec![0; puzzle.dots.len()],
+                ..SolverStats::default()
+            },
+        }
+    }
+
+    /// Only solutions starting with `dot_path` are searched, path which can't be drawn has none
+    pub fn with_prefix(mut self, dot_path: &[DotIndex]) -> Self {
+        self.prefix = dot_path.to_vec();
+        self
+    }
+    pub fn with_cancel(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn solve(self) -> Vec<Solution> {
+        self.solve_with_stats().0
+    }
+
+    pub fn solve_with_stats(self) -> (Vec<Solution>, SolverStats) {
+        let mut solutions = Vec::new();
+        let stats = self.for_each_solution(|dot_path, line_path| {
+            solutions.push(Solution {
+                dot_path: dot_path.to_vec(),
+                line_path: line_path.to_vec(),
+            });
+            ControlFlow::Continue(())
+        });
+        (solutions, stats)
+    }
+
+    /// Number of solutions, without keeping their paths
+    pub fn count(self) -> usize {
+        let mut count = 0;
+        self.for_each_solution(|_, _| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    /// Calls `visit` with dot and line paths of every solution as soon as it's found, until it breaks
+    pub fn for_each_solution(
+        mut self,
+        mut visit: impl FnMut(&[DotIndex], &[LineIndex]) -> ControlFlow<()>,
+    ) -> SolverStats {
+        if !self.prefix.is_empty() {
+            if self.follow_prefix() {
+                let last_dot = self.dot_path[self.dot_path.len() - 1];
+                self.search(last_dot, &mut visit);
+            }
+            return self.stats;
+        }
+        for &start_dot in &self.puzzle.start_dots {
+            if self.should_stop() {
+                break;
+            }
+            self.dot_path.push(start_dot);
+            self.visited[start_dot.0 as usize] = true;
+            self.search(start_dot, &mut visit);
+            self.visited[start_dot.0 as usize] = false;
+            self.dot_path.pop();
+        }
+        self.stats
+    }
+
+    /// Puts prefix into the path, false if it isn't a simple path from a start dot
+    fn follow_prefix(&mut self) -> bool {
+        let start_dot = self.prefix[0];
+        if !self.puzzle.start_dots.contains(&start_dot) {
+            return false;
+        }
+        self.dot_path.push(start_dot);
+        self.visited[start_dot.0 as usize] = true;
+        for i in 1..self.prefix.len() {
+            let (dot, next_dot) = (self.prefix[i - 1], self.prefix[i]);
+            let line = (self.near_lines[dot.0 as usize].iter()).find(|line| line.other(dot) == next_dot);
+            let Some(&line) = line else { return false };
+            if self.visited[next_dot.0 as usize] {
+                return false;
+            }
+            self.visited[next_dot.0 as usize] = true;
+            self.dot_path.push(next_dot);
+            self.line_path.push(line);
+        }
+        true
+    }
+
+    fn should_stop(&self) -> bool {
+        self.is_stopped
+            || self.limit.is_some_and(|limit| self.found >= limit)
+            || self.cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    fn search(&mut self, dot: DotIndex, visit: &mut impl FnMut(&[DotIndex], &[LineIndex]) -> ControlFlow<()>) {
+        self.stats.nodes += 1;
+        if self.puzzle.end_dots.contains(&dot) {
+            self.stats.complete_paths += 1;
+            match find_errors(self.puzzle, &self.dot_path, &self.line_path).len() {
+                0 => {
+                    self.found += 1;
+                    self.is_stopped = visit(&self.dot_path, &self.line_path).is_break();
+                }
+                1 => self.stats.near_solutions += 1,
+                _ => {}
+            }
+        }
+        self.stats.dot_visits[dot.0 as usize] += 1;
+        if !self.can_finish(dot) {
+            self.stats.pruned += 1;
+            return;
+        }
+
+        for i in 0..self.near_lines[dot.0 as usize].len() {
+            if self.should_stop() {
+                return;
+            }
+            let line = self.near_lines[dot.0 as usize][i];
+            let next_dot = line.other(dot);
+            if self.visited[next_dot.0 as usize] {
+                continue;
+            }
+
+            self.stats.dot_choices[dot.0 as usize] += 1;
+            self.visited[next_dot.0 as usize] = true;
+            self.dot_path.push(next_dot);
+            self.line_path.push(line);
+            self.search(next_dot, visit);
+            self.line_path.pop();
+            self.dot_path.pop();
+            self.visited[next_dot.0 as usize] = false;
+        }
+    }
+
+    /// Path ending at `dot` can still be continued to a solution: some end dot is reachable
+    /// through free dots, and so is every hexagon which isn't on the path yet
+    fn can_finish(&mut self, dot: DotIndex) -> bool {
+        self.reachable.fill(false);
+        self.reachable[dot.0 as usize] = true;
+        let mut is_end_reachable = false;
+        let mut stack = vec![dot];
+        while let Some(dot) = stack.pop() {
+            for &line in &self.near_lines[dot.0 as usize] {
+                let next_dot = line.other(dot);
+                let i = next_dot.0 as usize;
+                if self.visited[i] || self.reachable[i] {
+                    continue;
+                }
+                self.reachable[i] = true;
+                is_end_reachable |= self.is_end[i];
+                stack.push(next_dot);
+            }
+        }
+        if !is_end_reachable {
+            return false;
+        }
+
+        let is_free = |dot: DotIndex| self.reachable[dot.0 as usize];
+        let dots_ok = (self.hexagon_dots.iter()).all(|&dot| self.visited[dot.0 as usize] || is_free(dot));
+        // line with hexagon which isn't used yet must start from the end of the path or from free dot
+        let lines_ok = (self.hexagon_lines.iter())
+            .all(|line| self.line_path.contains(line) || (is_free(line.0) && is_free(line.1)));
+        dots_ok && lines_ok
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_puzzle_solutions() {
+        let puzzle = Puzzle::default();
+        let solutions = find_solutions(&puzzle, None);
+        assert!(!solutions.is_empty());
+        for solution in &solutions {
+            assert!(check_path(&puzzle, &solution.dot_path, &solution.line_path).is_ok());
+        }
+
+        let limited = find_solutions(&puzzle, Some(1));
+        assert_eq!(1, limited.len());
+
+        assert_eq!(solutions.len(), PuzzleSolver::new(&puzzle, None).count());
+        assert_eq!(2, PuzzleSolver::new(&puzzle, Some(2)).count());
+        let mut visited = 0;
+        PuzzleSolver::new(&puzzle, None).for_each_solution(|_, _| {
+            visited += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(1, visited);
+    }
+
+    #[test]
+    fn test_pruning_keeps_solutions() {
+        let puzzle = Puzzle::default();
+        let mut empty_puzzle = puzzle.clone();
+        empty_puzzle.dot_complexity.clear();
+        empty_puzzle.line_complexity.clear();
+        empty_puzzle.pane_complexity.clear();
+
+        let (solutions, stats) = PuzzleSolver::new(&puzzle, None).solve_with_stats();
+        let (empty_solutions, empty_stats) = PuzzleSolver::new(&empty_puzzle, None).solve_with_stats();
+        let expected: Vec<Solution> = (empty_solutions.into_iter())
+            .filter(|solution| check_path(&puzzle, &solution.dot_path, &solution.line_path).is_ok())
+            .collect();
+        assert_eq!(expected, solutions);
+        assert!(stats.pruned > 0);
+        assert!(stats.nodes < empty_stats.nodes);
+    }
+
+    #[test]
+    fn test_solve_with_prefix() {
+        let puzzle = Puzzle::default();
+        let solutions = find_solutions(&puzzle, None);
+        let prefix = &solutions[0].dot_path[..3];
+        let expected: Vec<Solution> = (solutions.iter())
+            .filter(|solution| solution.dot_path.starts_with(prefix))
+            .cloned()
+            .collect();
+        assert_eq!(expected, PuzzleSolver::new(&puzzle, None).with_prefix(prefix).solve());
+
+        let wrong_prefix = [puzzle.start_dots[0], puzzle.start_dots[0]];
+        let solver = PuzzleSolver::new(&puzzle, None).with_prefix(&wrong_prefix);
+        assert!(solver.solve().is_empty());
+
+        let cancel = AtomicBool::new(true);
+        assert!(PuzzleSolver::new(&puzzle, None).with_cancel(&cancel).solve().is_empty());
+    }
+}
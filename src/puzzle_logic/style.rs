@@ -0,0 +1,84 @@
+use super::*;
+use eframe::egui::Color32;
+
+/// Look of the panel, doesn't change its rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PuzzleStyle {
+    pub line_width: f32,
+    pub background_color: Color32,
+    pub puzzle_color: Color32,
+    pub solution_color: Color32,
+}
+
+/// The first one is used by new puzzles
+pub const STYLE_PRESETS: [(&str, PuzzleStyle); 4] = [
+    (
+        "Yellow",
+        PuzzleStyle {
+            line_width: 0.035,
+            background_color: Color32::from_rgb(228, 165, 0),
+            puzzle_color: Color32::from_rgb(61, 46, 3),
+            // solution_color: Color32::from_rgb(255, 234, 84),
+            solution_color: Color32::from_rgb(255, 255, 255),
+        },
+    ),
+    (
+        "Blue",
+        PuzzleStyle {
+            line_width: 0.035,
+            background_color: Color32::from_rgb(40, 90, 180),
+            puzzle_color: Color32::from_rgb(20, 40, 90),
+            solution_color: Color32::from_rgb(255, 255, 255),
+        },
+    ),
+    (
+        "Green",
+        PuzzleStyle {
+            line_width: 0.03,
+            background_color: Color32::from_rgb(70, 150, 60),
+            puzzle_color: Color32::from_rgb(25, 65, 20),
+            solution_color: Color32::from_rgb(240, 255, 150),
+        },
+    ),
+    (
+        "Night",
+        PuzzleStyle {
+            line_width: 0.025,
+            background_color: Color32::from_rgb(20, 20, 30),
+            puzzle_color: Color32::from_rgb(70, 70, 95),
+            solution_color: Color32::from_rgb(255, 140, 40),
+        },
+    ),
+];
+
+impl Puzzle {
+    pub fn style(&self) -> PuzzleStyle {
+        PuzzleStyle {
+            line_width: self.line_width,
+            background_color: self.background_color,
+            puzzle_color: self.puzzle_color,
+            solution_color: self.solution_color,
+        }
+    }
+    pub fn set_style(&mut self, style: PuzzleStyle) {
+        self.line_width = style.line_width;
+        self.background_color = style.background_color;
+        self.puzzle_color = style.puzzle_color;
+        self.solution_color = style.solution_color;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_style() {
+        let mut puzzle = Puzzle::default();
+        assert_eq!(STYLE_PRESETS[0].1, puzzle.style());
+        let (_, blue) = STYLE_PRESETS[1];
+        puzzle.set_style(blue);
+        assert_eq!(blue, puzzle.style());
+        assert_eq!(blue.background_color, puzzle.background_color);
+    }
+}
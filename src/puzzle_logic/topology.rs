@@ -0,0 +1,478 @@
+use super::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopologyError {
+    NoSuchDot(DotIndex),
+    NoSuchLine(LineIndex),
+    SameDot,
+    LineExists,
+    /// Lines can meet only at dots, otherwise panes can't be found
+    LinesCross,
+    /// Line goes through dot which isn't its end
+    DotOnLine(DotIndex),
+    /// End stub must go outside of all panes
+    NotOnBorder,
+    NotEndDot(DotIndex),
+}
+
+impl std::fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyError::NoSuchDot(dot) => write!(f, "there is no dot {dot}"),
+            TopologyError::NoSuchLine(line) => write!(f, "there is no {line}"),
+            TopologyError::SameDot => write!(f, "line must connect two different dots"),
+            TopologyError::LineExists => write!(f, "dots are already connected"),
+            TopologyError::LinesCross => write!(f, "lines can't cross or overlap each other"),
+            TopologyError::DotOnLine(dot) => write!(f, "line can't go through dot {dot}"),
+            TopologyError::NotOnBorder => write!(f, "end must point outside of the panel"),
+            TopologyError::NotEndDot(dot) => write!(f, "dot {dot} is not an end"),
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+/// Points closer than this are the same point, in puzzle coords
+const TOUCH_EPSILON: f32 = 1e-4;
+
+fn cross(o: Dot, a: Dot, b: Dot) -> f32 {
+    let (oa, ob) = (a - o, b - o);
+    oa.x * ob.y - oa.y * ob.x
+}
+
+/// Point lies on segment and isn't one of its ends
+pub(super) fn point_inside_segment(point: Dot, (s1, s2): (Dot, Dot)) -> bool {
+    let length = (s2 - s1).length();
+    if length < TOUCH_EPSILON {
+        return false;
+    }
+    let distance = cross(s1, s2, point).abs() / length;
+    let along = (point - s1).scalar(&(s2 - s1)) / length;
+    distance < TOUCH_EPSILON && along > TOUCH_EPSILON && along < length - TOUCH_EPSILON
+}
+
+/// Do segments have common point which isn't their common end,
+/// collinear segments overlapping or touching in the middle also count
+pub(super) fn segments_cross((a1, a2): (Dot, Dot), (b1, b2): (Dot, Dot)) -> bool {
+    let d1 = cross(b1, b2, a1);
+    let d2 = cross(b1, b2, a2);
+    let d3 = cross(a1, a2, b1);
+    let d4 = cross(a1, a2, b2);
+    let is_proper_cross = d1 * d2 < 0.0 && d3 * d4 < 0.0;
+    // every other common point is an end of one segment inside the other one
+    is_proper_cross
+        || point_inside_segment(a1, (b1, b2))
+        || point_inside_segment(a2, (b1, b2))
+        || point_inside_segment(b1, (a1, a2))
+        || point_inside_segment(b2, (a1, a2))
+}
+
+fn polygon_area(polygon: &[Dot]) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let (p1, p2) = (polygon[i], polygon[(i + 1) % n]);
+            p1.x * p2.y - p2.x * p1.y
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+fn polygon_centroid(polygon: &[Dot]) -> Dot {
+    let n = polygon.len();
+    let area = polygon_area(polygon);
+    let mut center = Dot::ZERO;
+    for i in 0..n {
+        let (p1, p2) = (polygon[i], polygon[(i + 1) % n]);
+        let cross = p1.x * p2.y - p2.x * p1.y;
+        center = center + (p1 + p2).scale(cross);
+    }
+    center.scale(1.0 / (6.0 * area))
+}
+
+fn polygon_contains(polygon: &[Dot], point: Dot) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (p1, p2) = (polygon[i], polygon[(i + 1) % n]);
+        if (p1.y > point.y) != (p2.y > point.y) {
+            let x = p1.x + (point.y - p1.y) / (p2.y - p1.y) * (p2.x - p1.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Closed regions of plane graph, each one becomes a pane
+struct Face {
+    polygon: Vec<Dot>,
+    /// Lines on the border, with dot the border goes from
+    border: Vec<(DotIndex, LineIndex)>,
+}
+
+impl Puzzle {
+    fn check_dot(&self, dot: DotIndex) -> Result<(), TopologyError> {
+        if (dot.0 as usize) < self.dots.len() {
+            Ok(())
+        } else {
+            Err(TopologyError::NoSuchDot(dot))
+        }
+    }
+    /// Line between dots in any direction
+    pub fn find_line(&self, dot1: DotIndex, dot2: DotIndex) -> Option<LineIndex> {
+        (self.lines.iter())
+            .find(|line| line.contains(dot1) && line.other(dot1) == dot2)
+            .copied()
+    }
+    /// Segment crosses or overlaps other line, or goes through a dot
+    fn check_segment(&self, segment: (DotIndex, DotIndex)) -> Result<(), TopologyError> {
+        let get_dot = |dot: DotIndex| self.dots[dot.0 as usize];
+        let positions = (get_dot(segment.0), get_dot(segment.1));
+        let crosses = (self.lines.iter())
+            .filter(|line| !(line.contains(segment.0) && line.contains(segment.1)))
+            .any(|line| segments_cross(positions, (get_dot(line.0), get_dot(line.1))));
+        if crosses {
+            return Err(TopologyError::LinesCross);
+        }
+        let dot_on_line = (0..self.dots.len())
+            .map(|i| DotIndex(i as u16))
+            .find(|&dot| point_inside_segment(get_dot(dot), positions));
+        match dot_on_line {
+            Some(dot) => Err(TopologyError::DotOnLine(dot)),
+            None => Ok(()),
+        }
+    }
+
+    /// Lines of dot are fine and dot isn't on other line
+    fn check_moved_dot(&self, dot: DotIndex) -> Result<(), TopologyError> {
+        for line in self.lines.iter().filter(|line| line.contains(dot)) {
+            self.check_segment((line.0, line.1))?;
+        }
+        let get_dot = |dot: DotIndex| self.dots[dot.0 as usize];
+        let pos = get_dot(dot);
+        if (self.lines.iter()).any(|line| point_inside_segment(pos, (get_dot(line.0), get_dot(line.1)))) {
+            return Err(TopologyError::DotOnLine(dot));
+        }
+        Ok(())
+    }
+
+    /// Adds dot without lines
+    pub fn add_dot(&mut self, pos: Dot) -> DotIndex {
+        self.dots.push(pos);
+        DotIndex((self.dots.len() - 1) as u16)
+    }
+
+    /// Moves dot, move which makes lines cross is rejected
+    pub fn move_dot(&mut self, dot: DotIndex, pos: Dot) -> Result<(), TopologyError> {
+        self.check_dot(dot)?;
+        let old_pos = std::mem::replace(&mut self.dots[dot.0 as usize], pos);
+        let result = self.check_moved_dot(dot);
+        if let Err(err) = result {
+            self.dots[dot.0 as usize] = old_pos;
+            return Err(err);
+        }
+        self.recompute_panes();
+        Ok(())
+    }
+
+    pub fn add_line(&mut self, dot1: DotIndex, dot2: DotIndex) -> Result<LineIndex, TopologyError> {
+        self.check_dot(dot1)?;
+        self.check_dot(dot2)?;
+        if dot1 == dot2 {
+            return Err(TopologyError::SameDot);
+        }
+        if self.find_line(dot1, dot2).is_some() {
+            return Err(TopologyError::LineExists);
+        }
+        self.check_segment((dot1, dot2))?;
+        let line = LineIndex(dot1, dot2);
+        self.lines.push(line);
+        self.recompute_panes();
+        Ok(line)
+    }
+
+    pub fn remove_line(&mut self, line: LineIndex) -> Result<(), TopologyError> {
+        let index = (self.lines.iter().position(|x| *x == line)).ok_or(TopologyError::NoSuchLine(line))?;
+        self.lines.remove(index);
+        self.line_complexity.remove(&line);
+        self.recompute_panes();
+        Ok(())
+    }
+
+    /// Removes dot with its lines, bigger dot indexes are shifted down
+    pub fn remove_dot(&mut self, dot: DotIndex) -> Result<(), TopologyError> {
+        self.check_dot(dot)?;
+        self.dots.remove(dot.0 as usize);
+        let shift = |x: DotIndex| if x > dot { DotIndex(x.0 - 1) } else { x };
+        let shift_line = |line: LineIndex| LineIndex(shift(line.0), shift(line.1));
+
+        self.lines.retain(|line| !line.contains(dot));
+        self.lines.iter_mut().for_each(|line| *line = shift_line(*line));
+        for dots in [&mut self.start_dots, &mut self.end_dots] {
+            dots.retain(|x| *x != dot);
+            dots.iter_mut().for_each(|x| *x = shift(*x));
+        }
+        self.dot_complexity.remove(&dot);
+        self.dot_complexity = (self.dot_complexity.drain())
+            .map(|(x, value)| (shift(x), value))
+            .collect();
+        self.line_complexity.retain(|line, _| !line.contains(dot));
+        self.line_complexity = (self.line_complexity.drain())
+            .map(|(line, value)| (shift_line(line), value))
+            .collect();
+        self.recompute_panes();
+        Ok(())
+    }
+
+    /// Makes dot a start dot or not, returns true if it's a start dot now
+    pub fn toggle_start_dot(&mut self, dot: DotIndex) -> Result<bool, TopologyError> {
+        self.check_dot(dot)?;
+        if let Some(index) = self.start_dots.iter().position(|x| *x == dot) {
+            self.start_dots.remove(index);
+            Ok(false)
+        } else {
+            self.start_dots.push(dot);
+            Ok(true)
+        }
+    }
+
+    /// Adds end dot half a cell away from dot, connected to it by a short line
+    pub fn add_end_stub(&mut self, dot: DotIndex, direction: Dot) -> Result<DotIndex, TopologyError> {
+        self.check_dot(dot)?;
+        let pos = self.dots[dot.0 as usize] + direction.scale(self.cell_size * 0.5 / direction.length());
+        let faces = self.find_faces();
+        if faces.iter().any(|face| polygon_contains(&face.polygon, pos)) {
+            return Err(TopologyError::NotOnBorder);
+        }
+        let end_dot = self.add_dot(pos);
+        if let Err(err) = self.check_segment((end_dot, dot)) {
+            self.dots.pop();
+            return Err(err);
+        }
+        self.lines.push(LineIndex(end_dot, dot));
+        self.end_dots.push(end_dot);
+        Ok(end_dot)
+    }
+
+    /// Removes end dot together with its line
+    pub fn remove_end_stub(&mut self, end_dot: DotIndex) -> Result<(), TopologyError> {
+        if !self.end_dots.contains(&end_dot) {
+            return Err(TopologyError::NotEndDot(end_dot));
+        }
+        self.remove_dot(end_dot)
+    }
+
+    /// Finds bounded faces by walking around every line side
+    fn find_faces(&self) -> Vec<Face> {
+        let get_dot = |dot: DotIndex| self.dots[dot.0 as usize];
+        // lines from every dot, sorted counterclockwise
+        let mut around: Vec<Vec<(DotIndex, LineIndex)>> = vec![Vec::new(); self.dots.len()];
+        for &line in &self.lines {
+            around[line.0.0 as usize].push((line.1, line));
+            around[line.1.0 as usize].push((line.0, line));
+        }
+        for (i, nears) in around.iter_mut().enumerate() {
+            let center = self.dots[i];
+            let angle = |dot: DotIndex| {
+                let d = get_dot(dot) - center;
+                d.y.atan2(d.x)
+            };
+            nears.sort_by(|(dot1, _), (dot2, _)| angle(*dot1).total_cmp(&angle(*dot2)));
+        }
+
+        let mut visited: Vec<(DotIndex, DotIndex)> = Vec::new();
+        let mut faces = Vec::new();
+        for &line in &self.lines {
+            for (from, to) in [(line.0, line.1), (line.1, line.0)] {
+                if visited.contains(&(from, to)) {
+                    continue;
+                }
+                let mut border = Vec::new();
+                let (mut from, mut to, mut line) = (from, to, line);
+                while !visited.contains(&(from, to)) {
+                    visited.push((from, to));
+                    border.push((from, line));
+                    // face stays on the left, so turn to the next line clockwise
+                    let nears = &around[to.0 as usize];
+                    let back = nears.iter().position(|(dot, _)| *dot == from).unwrap_or_default();
+                    let (next, next_line) = nears[(back + nears.len() - 1) % nears.len()];
+                    (from, to, line) = (to, next, next_line);
+                }
+                let polygon: Vec<Dot> = border.iter().map(|(dot, _)| get_dot(*dot)).collect();
+                if polygon_area(&polygon) > f32::EPSILON {
+                    faces.push(Face { polygon, border });
+                }
+            }
+        }
+        faces
+    }
+
+    /// Outline of every pane, empty if pane center isn't inside any face
+    pub fn pane_polygons(&self) -> Vec<Vec<Dot>> {
+        let faces = self.find_faces();
+        (self.panes.iter())
+            .map(|&center| {
+                (faces.iter())
+                    .find(|face| polygon_contains(&face.polygon, center))
+                    .map(|face| face.polygon.clone())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Rebuilds `panes` and `pane_nears` from lines, pane symbols stay in panes under them
+    pub fn recompute_panes(&mut self) {
+        // same order as default grid: rows from bottom, left to right
+        let key = |dot: &Dot| ((dot.y * 1e4).round() as i64, (dot.x * 1e4).round() as i64);
+        let mut faces: Vec<(Dot, Face)> = (self.find_faces().into_iter())
+            .map(|face| (polygon_centroid(&face.polygon), face))
+            .collect();
+        faces.sort_by_key(|(center, _)| key(center));
+
+        let mut line_faces: HashMap<LineIndex, Vec<PaneIndex>> = HashMap::new();
+        for (i, (_, face)) in faces.iter().enumerate() {
+            for (_, line) in &face.border {
+                line_faces.entry(*line).or_default().push(PaneIndex(i as u16));
+            }
+        }
+        let mut pane_nears = vec![Vec::new(); faces.len()];
+        for &line in &self.lines {
+            if let Some(&[pane1, pane2]) = line_faces.get(&line).map(|x| x.as_slice())
+                && pane1 != pane2
+            {
+                pane_nears[pane1.0 as usize].push((line, pane2));
+                pane_nears[pane2.0 as usize].push((line, pane1));
+            }
+        }
+
+        let old_panes = std::mem::take(&mut self.panes);
+        self.pane_complexity = (self.pane_complexity.drain())
+            .filter_map(|(pane, complexity)| {
+                let old_center = *old_panes.get(pane.0 as usize)?;
+                let new_pane = (faces.iter()).position(|(_, face)| polygon_contains(&face.polygon, old_center))?;
+                Some((PaneIndex(new_pane as u16), complexity))
+            })
+            .collect();
+        self.panes = faces.iter().map(|(center, _)| *center).collect();
+        self.pane_nears = pane_nears;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn near_set(puzzle: &Puzzle) -> Vec<Vec<(LineIndex, PaneIndex)>> {
+        let mut nears = puzzle.pane_nears.clone();
+        nears.iter_mut().for_each(|x| x.sort());
+        nears
+    }
+
+    #[test]
+    fn test_recompute_default_panes() {
+        let puzzle = Puzzle::default();
+        let mut recomputed = puzzle.clone();
+        recomputed.recompute_panes();
+        assert_eq!(puzzle.panes.len(), recomputed.panes.len());
+        for (pane1, pane2) in puzzle.panes.iter().zip(&recomputed.panes) {
+            assert!((*pane1 - *pane2).length() < 1e-5);
+        }
+        assert_eq!(near_set(&puzzle), near_set(&recomputed));
+        assert_eq!(puzzle.pane_complexity, recomputed.pane_complexity);
+
+        let polygons = puzzle.pane_polygons();
+        assert_eq!(puzzle.panes.len(), polygons.len());
+        for (polygon, &center) in polygons.iter().zip(&puzzle.panes) {
+            assert_eq!(4, polygon.len());
+            assert!((polygon_centroid(polygon) - center).length() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_edit_topology() {
+        let mut puzzle = Puzzle::default();
+        // splitting the first pane with diagonal
+        assert_eq!(
+            Err(TopologyError::LineExists),
+            puzzle.add_line(DotIndex(1), DotIndex(0))
+        );
+        assert_eq!(
+            Err(TopologyError::LinesCross),
+            puzzle.add_line(DotIndex(1), DotIndex(10))
+        );
+        puzzle.add_line(DotIndex(0), DotIndex(6)).unwrap();
+        assert_eq!(17, puzzle.panes.len());
+
+        // removing middle line joins two panes, squares stay where they were
+        puzzle.remove_line(LineIndex(DotIndex(17), DotIndex(22))).unwrap();
+        assert_eq!(16, puzzle.panes.len());
+        assert_eq!(2, puzzle.pane_complexity.len());
+
+        assert_eq!(
+            Err(TopologyError::LinesCross),
+            puzzle.move_dot(DotIndex(6), Dot::new(0.6, 0.1))
+        );
+        puzzle.move_dot(DotIndex(6), Dot::new(0.35, 0.3)).unwrap();
+
+        let dot = puzzle.add_dot(Dot::new(0.95, 0.95));
+        puzzle.add_line(dot, DotIndex(24)).unwrap();
+        puzzle.remove_dot(DotIndex(0)).unwrap();
+        assert!(puzzle.start_dots.is_empty());
+        assert_eq!(
+            Some(&DotComplexity::BlackHexagon),
+            puzzle.dot_complexity.get(&DotIndex(1))
+        );
+        assert!(puzzle.lines.contains(&LineIndex(DotIndex(dot.0 - 1), DotIndex(23))));
+        assert_eq!(14, puzzle.panes.len());
+    }
+
+    #[test]
+    fn test_overlapping_lines() {
+        let mut puzzle = Puzzle::default();
+        // goes along line 0-1 and through dot 1
+        for dot in [DotIndex(2), DotIndex(10)] {
+            assert_eq!(Err(TopologyError::LinesCross), puzzle.add_line(DotIndex(0), dot));
+        }
+        assert_eq!(16, puzzle.panes.len());
+
+        // dot without lines in the middle of new line
+        let mut puzzle = Puzzle::grid(3, 1);
+        let middle = puzzle.add_dot(Dot::new(0.5, 0.7));
+        let far = puzzle.add_dot(Dot::new(0.5, 0.9));
+        assert_eq!(Err(TopologyError::DotOnLine(middle)), puzzle.add_line(DotIndex(1), far));
+        // dot can't be moved onto a line
+        assert_eq!(
+            Err(TopologyError::DotOnLine(middle)),
+            puzzle.move_dot(middle, puzzle.dots[0].interp(&puzzle.dots[1], 0.5))
+        );
+
+        let a = (Dot::new(0.0, 0.0), Dot::new(1.0, 0.0));
+        assert!(segments_cross(a, (Dot::new(0.5, 0.0), Dot::new(2.0, 0.0))));
+        assert!(segments_cross(a, (Dot::new(0.0, 0.0), Dot::new(0.5, 0.0))));
+        assert!(segments_cross(a, (Dot::new(0.5, 0.0), Dot::new(0.5, 1.0))));
+        assert!(!segments_cross(a, (Dot::new(1.0, 0.0), Dot::new(2.0, 0.0))));
+        assert!(!segments_cross(a, (Dot::new(0.0, 0.0), Dot::new(0.0, 1.0))));
+    }
+
+    #[test]
+    fn test_start_and_end_tools() {
+        let mut puzzle = Puzzle::default();
+        assert_eq!(Ok(true), puzzle.toggle_start_dot(DotIndex(12)));
+        assert_eq!(Ok(false), puzzle.toggle_start_dot(DotIndex(0)));
+        assert_eq!(vec![DotIndex(12)], puzzle.start_dots);
+
+        assert_eq!(Err(TopologyError::NotOnBorder), puzzle.add_end_stub(DotIndex(12), Dot::new(1.0, 0.0)));
+        assert_eq!(Err(TopologyError::NotOnBorder), puzzle.add_end_stub(DotIndex(4), Dot::new(1.0, -1.0)));
+        let end_dot = puzzle.add_end_stub(DotIndex(4), Dot::new(-1.0, 1.0)).unwrap();
+        assert_eq!(vec![DotIndex(25), end_dot], puzzle.end_dots);
+        assert_eq!(Some(&LineIndex(end_dot, DotIndex(4))), puzzle.lines.last());
+
+        assert_eq!(Err(TopologyError::NotEndDot(DotIndex(24))), puzzle.remove_end_stub(DotIndex(24)));
+        puzzle.remove_end_stub(DotIndex(25)).unwrap();
+        assert_eq!(vec![DotIndex(25)], puzzle.end_dots);
+        assert_eq!(16, puzzle.panes.len());
+    }
+}
@@ -0,0 +1,214 @@
+use super::topology::{point_inside_segment, segments_cross};
+use super::*;
+use std::collections::HashSet;
+
+/// Problem of puzzle structure, found without solving
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationWarning {
+    NoStart,
+    NoEnd,
+    StartIsEnd(DotIndex),
+    /// Line, start, end or symbol uses dot which doesn't exist
+    MissingDot(DotIndex),
+    /// Symbol is on line which doesn't exist
+    MissingLine(LineIndex),
+    MissingPane(PaneIndex),
+    /// `pane_nears` has this many entries instead of one for every pane
+    PaneNearsCount(usize),
+    /// Pane neighbours refer to pane which doesn't exist
+    MissingNearPane(PaneIndex),
+    /// Pane border is on line which doesn't exist
+    MissingNearLine(LineIndex),
+    DuplicateLine(LineIndex),
+    LinesCross(LineIndex, LineIndex),
+    /// Line goes through dot which isn't its end
+    DotOnLine(DotIndex, LineIndex),
+    IsolatedDot(DotIndex),
+}
+
+impl std::fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationWarning::NoStart => write!(f, "there is no start"),
+            ValidationWarning::NoEnd => write!(f, "there is no end"),
+            ValidationWarning::StartIsEnd(dot) => write!(f, "dot {dot} is both start and end"),
+            ValidationWarning::MissingDot(dot) => write!(f, "there is no dot {dot}"),
+            ValidationWarning::MissingLine(line) => write!(f, "symbol is on missing {line}"),
+            ValidationWarning::MissingPane(pane) => write!(f, "symbol is on missing pane {}", pane.0),
+            ValidationWarning::PaneNearsCount(count) => write!(f, "there are neighbours for {count} panes"),
+            ValidationWarning::MissingNearPane(pane) => write!(f, "neighbour pane {} is missing", pane.0),
+            ValidationWarning::MissingNearLine(line) => write!(f, "pane border {line} is missing"),
+            ValidationWarning::DuplicateLine(line) => write!(f, "{line} is duplicated"),
+            ValidationWarning::LinesCross(line1, line2) => write!(f, "{line1} crosses {line2}"),
+            ValidationWarning::DotOnLine(dot, line) => write!(f, "{line} goes through dot {dot}"),
+            ValidationWarning::IsolatedDot(dot) => write!(f, "dot {dot} has no lines"),
+        }
+    }
+}
+
+impl ValidationWarning {
+    /// Puzzle with such warning can't be solved or drawn without panic
+    pub fn is_broken(&self) -> bool {
+        matches!(
+            self,
+            ValidationWarning::MissingDot(_)
+                | ValidationWarning::MissingLine(_)
+                | ValidationWarning::MissingPane(_)
+                | ValidationWarning::PaneNearsCount(_)
+                | ValidationWarning::MissingNearPane(_)
+                | ValidationWarning::MissingNearLine(_)
+        )
+    }
+}
+
+pub fn validate_puzzle(puzzle: &Puzzle) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    let mut missing_dots: Vec<DotIndex> = (puzzle.lines.iter())
+        .flat_map(|line| [line.0, line.1])
+        .chain(puzzle.start_dots.iter().copied())
+        .chain(puzzle.end_dots.iter().copied())
+        .chain(puzzle.dot_complexity.keys().copied())
+        .filter(|dot| dot.0 as usize >= puzzle.dots.len())
+        .collect();
+    missing_dots.sort();
+    missing_dots.dedup();
+    if !missing_dots.is_empty() {
+        // everything else needs positions of dots
+        warnings.extend(missing_dots.into_iter().map(ValidationWarning::MissingDot));
+        return warnings;
+    }
+
+    if puzzle.start_dots.is_empty() {
+        warnings.push(ValidationWarning::NoStart);
+    }
+    if puzzle.end_dots.is_empty() {
+        warnings.push(ValidationWarning::NoEnd);
+    }
+    for &dot in &puzzle.start_dots {
+        if puzzle.end_dots.contains(&dot) {
+            warnings.push(ValidationWarning::StartIsEnd(dot));
+        }
+    }
+
+    let mut line_symbols: Vec<LineIndex> = puzzle.line_complexity.keys().copied().collect();
+    line_symbols.sort();
+    for line in line_symbols {
+        if !puzzle.lines.contains(&line) {
+            warnings.push(ValidationWarning::MissingLine(line));
+        }
+    }
+    let mut pane_symbols: Vec<PaneIndex> = puzzle.pane_complexity.keys().copied().collect();
+    pane_symbols.sort();
+    for pane in pane_symbols {
+        if pane.0 as usize >= puzzle.panes.len() {
+            warnings.push(ValidationWarning::MissingPane(pane));
+        }
+    }
+    if puzzle.pane_nears.len() != puzzle.panes.len() {
+        warnings.push(ValidationWarning::PaneNearsCount(puzzle.pane_nears.len()));
+    }
+    let nears = || puzzle.pane_nears.iter().flatten();
+    let mut missing_panes: Vec<PaneIndex> = (nears())
+        .map(|(_, pane)| *pane)
+        .filter(|pane| pane.0 as usize >= puzzle.panes.len())
+        .collect();
+    missing_panes.sort();
+    missing_panes.dedup();
+    warnings.extend(missing_panes.into_iter().map(ValidationWarning::MissingNearPane));
+    let mut missing_lines: Vec<LineIndex> = (nears())
+        .map(|(line, _)| *line)
+        .filter(|line| !puzzle.lines.contains(line))
+        .collect();
+    missing_lines.sort();
+    missing_lines.dedup();
+    warnings.extend(missing_lines.into_iter().map(ValidationWarning::MissingNearLine));
+
+    let mut connected = HashSet::new();
+    for &line in &puzzle.lines {
+        let key = (line.0.min(line.1), line.0.max(line.1));
+        if !connected.insert(key) {
+            warnings.push(ValidationWarning::DuplicateLine(line));
+        }
+    }
+
+    let get_dot = |dot: DotIndex| puzzle.dots[dot.0 as usize];
+    let segment = |line: LineIndex| (get_dot(line.0), get_dot(line.1));
+    for (i, &line1) in puzzle.lines.iter().enumerate() {
+        for &line2 in &puzzle.lines[i + 1..] {
+            if segments_cross(segment(line1), segment(line2)) {
+                warnings.push(ValidationWarning::LinesCross(line1, line2));
+            }
+        }
+    }
+    for &line in &puzzle.lines {
+        for (i, &dot) in puzzle.dots.iter().enumerate() {
+            if point_inside_segment(dot, segment(line)) {
+                warnings.push(ValidationWarning::DotOnLine(DotIndex(i as u16), line));
+            }
+        }
+    }
+
+    for i in 0..puzzle.dots.len() {
+        let dot = DotIndex(i as u16);
+        if !puzzle.lines.iter().any(|line| line.contains(dot)) {
+            warnings.push(ValidationWarning::IsolatedDot(dot));
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_puzzle() {
+        let mut puzzle = Puzzle::default();
+        assert!(validate_puzzle(&puzzle).is_empty());
+
+        puzzle.start_dots.clear();
+        puzzle.lines.push(LineIndex(DotIndex(1), DotIndex(0)));
+        // diagonal crosses the other diagonal of the same pane
+        puzzle.lines.push(LineIndex(DotIndex(0), DotIndex(6)));
+        puzzle.lines.push(LineIndex(DotIndex(1), DotIndex(5)));
+        puzzle
+            .pane_complexity
+            .insert(PaneIndex(99), PaneComplexity::Square(ComplexityColor::Black));
+        puzzle.dots.push(Dot::new(0.5, 0.95));
+        let expected = vec![
+            ValidationWarning::NoStart,
+            ValidationWarning::MissingPane(PaneIndex(99)),
+            ValidationWarning::DuplicateLine(LineIndex(DotIndex(1), DotIndex(0))),
+            ValidationWarning::LinesCross(LineIndex(DotIndex(0), DotIndex(6)), LineIndex(DotIndex(1), DotIndex(5))),
+            ValidationWarning::IsolatedDot(DotIndex(26)),
+        ];
+        assert_eq!(expected, validate_puzzle(&puzzle));
+
+        let mut broken_nears = Puzzle::default();
+        broken_nears.pane_nears[0].push((LineIndex(DotIndex(0), DotIndex(24)), PaneIndex(0)));
+        broken_nears.pane_nears[1].push((LineIndex(DotIndex(5), DotIndex(6)), PaneIndex(16)));
+        broken_nears.pane_nears.pop();
+        let expected = vec![
+            ValidationWarning::PaneNearsCount(15),
+            ValidationWarning::MissingNearPane(PaneIndex(16)),
+            ValidationWarning::MissingNearLine(LineIndex(DotIndex(0), DotIndex(24))),
+        ];
+        let warnings = validate_puzzle(&broken_nears);
+        assert_eq!(expected, warnings);
+        assert!(warnings.iter().all(ValidationWarning::is_broken));
+
+        // collinear lines overlap along the first column
+        let mut overlapping = Puzzle::default();
+        overlapping.lines.push(LineIndex(DotIndex(0), DotIndex(2)));
+        let warnings = validate_puzzle(&overlapping);
+        let (line, overlap) = (LineIndex(DotIndex(0), DotIndex(1)), LineIndex(DotIndex(0), DotIndex(2)));
+        assert!(warnings.contains(&ValidationWarning::LinesCross(line, overlap)));
+        assert!(warnings.contains(&ValidationWarning::DotOnLine(DotIndex(1), overlap)));
+
+        puzzle.end_dots.push(DotIndex(40));
+        assert_eq!(
+            vec![ValidationWarning::MissingDot(DotIndex(40))],
+            validate_puzzle(&puzzle)
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::puzzle_logic::*;
+use eframe::egui::Color32;
+use std::fmt::Write;
+
+/// Same proportions as `EguiDrawer`
+const START_DOT_SCALE: f32 = 3.0;
+const PANE_SCALE: f32 = 2.0;
+
+struct SvgWriter {
+    size: f32,
+    text: String,
+}
+
+fn svg_color(color: Color32) -> String {
+    let [r, g, b, _] = color.to_srgba_unmultiplied();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+impl SvgWriter {
+    fn point(&self, dot: Dot) -> (f32, f32) {
+        // svg y axis goes down
+        (dot.x * self.size, (1.0 - dot.y) * self.size)
+    }
+    fn circle(&mut self, dot: Dot, width: f32, color: Color32) {
+        let (x, y) = self.point(dot);
+        let r = width / 2.0;
+        let fill = svg_color(color);
+        let _ = writeln!(self.text, r#"<circle cx="{x}" cy="{y}" r="{r}" fill="{fill}"/>"#);
+    }
+    fn line(&mut self, line: (Dot, Dot), width: f32, color: Color32) {
+        let (x1, y1) = self.point(line.0);
+        let (x2, y2) = self.point(line.1);
+        let stroke = svg_color(color);
+        let _ = writeln!(
+            self.text,
+            r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{stroke}" stroke-width="{width}"/>"#
+        );
+    }
+    fn hexagon(&mut self, dot: Dot, width: f32) {
+        let (x, y) = self.point(dot);
+        let r = width * 0.45;
+        let points: Vec<String> = (0..6)
+            .map(|i| {
+                let angle = std::f32::consts::PI / 3.0 * i as f32;
+                format!("{},{}", x + r * angle.cos(), y + r * angle.sin())
+            })
+            .collect();
+        let points = points.join(" ");
+        let _ = writeln!(
+            self.text,
+            r#"<polygon points="{points}" fill="{}"/>"#,
+            svg_color(Color32::BLACK)
+        );
+    }
+    fn square(&mut self, dot: Dot, width: f32, color: ComplexityColor) {
+        let (x, y) = self.point(dot);
+        let (x, y) = (x - width / 2.0, y - width / 2.0);
+        let rx = width / 3.0;
+        let fill = svg_color(match color {
+            ComplexityColor::White => Color32::WHITE,
+            ComplexityColor::Black => Color32::BLACK,
+        });
+        let _ = writeln!(
+            self.text,
+            r#"<rect x="{x}" y="{y}" width="{width}" height="{width}" rx="{rx}" fill="{fill}"/>"#
+        );
+    }
+}
+
+/// Draws puzzle as square svg image with side of `size` pixels
+pub fn render_svg(puzzle: &Puzzle, size: f32) -> String {
+    let mut svg = SvgWriter {
+        size,
+        text: String::new(),
+    };
+    let _ = writeln!(
+        svg.text,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+    let background = svg_color(puzzle.background_color);
+    let _ = writeln!(
+        svg.text,
+        r#"<rect width="{size}" height="{size}" fill="{background}"/>"#
+    );
+
+    let color = puzzle.puzzle_color;
+    let width = puzzle.line_width * size;
+    let get_dot = |dot: DotIndex| puzzle.dots[dot.0 as usize];
+    for &dot in &puzzle.dots {
+        svg.circle(dot, width, color);
+    }
+    for &start_dot in &puzzle.start_dots {
+        svg.circle(get_dot(start_dot), width * START_DOT_SCALE, color);
+    }
+    for &line in &puzzle.lines {
+        svg.line((get_dot(line.0), get_dot(line.1)), width, color);
+    }
+
+    // sorted, so the same puzzle always gives the same file
+    for symbol in puzzle_symbols(puzzle) {
+        match symbol {
+            Symbol::Dot(dot, DotComplexity::BlackHexagon) => svg.hexagon(get_dot(dot), width),
+            Symbol::Line(line, LineComplexity::BlackHexagon) => {
+                svg.hexagon((get_dot(line.0) + get_dot(line.1)).scale(0.5), width)
+            }
+            Symbol::Line(line, LineComplexity::LineBreak) => {
+                let (dot1, dot2) = (get_dot(line.0), get_dot(line.1));
+                let gap = (
+                    dot1.interp(&dot2, LINE_BREAK_WIDTH),
+                    dot1.interp(&dot2, 1.0 - LINE_BREAK_WIDTH),
+                );
+                svg.line(gap, width + 2.0, puzzle.background_color);
+            }
+            Symbol::Pane(pane, PaneComplexity::Square(color)) => {
+                svg.square(puzzle.panes[pane.0 as usize], width * PANE_SCALE, color)
+            }
+        }
+    }
+    svg.text.push_str("</svg>\n");
+    svg.text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_svg() {
+        let puzzle = Puzzle::default();
+        let svg = render_svg(&puzzle, 600.0);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(puzzle.lines.len() + 1, svg.matches("<line").count());
+        assert_eq!(2, svg.matches("<polygon").count());
+        assert_eq!(svg, render_svg(&puzzle, 600.0));
+    }
+}